@@ -25,20 +25,27 @@ fn parse(input: &str) -> Vec<Rotation> {
     input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
+impl Rotation {
+    /// The signed number of clicks, negative for rotations to the left.
+    fn amount(self) -> i32 {
+        match self {
+            Rotation::L(amount) => -amount,
+            Rotation::R(amount) => amount,
+        }
+    }
+}
+
+/// A dial with `SIZE` positions, numbered from 0 to `SIZE - 1`.
 #[derive(Debug, Clone)]
-struct Dial(i32);
+struct Dial<const SIZE: i32 = 100>(i32);
 
-impl Dial {
+impl<const SIZE: i32> Dial<SIZE> {
     fn is_zero(&self) -> bool {
         self.0 == 0
     }
 
     fn rotate_part1(&mut self, rotation: Rotation, zeros: &mut usize) {
-        self.0 = match rotation {
-            Rotation::L(amount) => self.0 - amount,
-            Rotation::R(amount) => self.0 + amount,
-        }
-        .rem_euclid(100);
+        self.0 = (self.0 + rotation.amount()).rem_euclid(SIZE);
         if self.is_zero() {
             *zeros += 1;
         }
@@ -47,7 +54,7 @@ impl Dial {
 
 #[aoc(day1, part1)]
 fn part1(input: &[Rotation]) -> usize {
-    let mut dial = Dial(50);
+    let mut dial: Dial = Dial(50);
     let mut zero_times = 0usize;
     for &rotation in input {
         dial.rotate_part1(rotation, &mut zero_times);
@@ -55,29 +62,27 @@ fn part1(input: &[Rotation]) -> usize {
     zero_times
 }
 
-impl Dial {
+impl<const SIZE: i32> Dial<SIZE> {
     fn rotate_part2(&mut self, rotation: Rotation, clicks: &mut usize) {
-        let (step, amount) = match rotation {
-            Rotation::L(amount) => (-1, amount as usize),
-            Rotation::R(amount) => (1, amount as usize),
+        *clicks += Self::zero_crossings(self.0, rotation.amount()) as usize;
+        self.0 = (self.0 + rotation.amount()).rem_euclid(SIZE);
+    }
+
+    /// How many times the dial points at zero while turning `amount` clicks from `position`.
+    fn zero_crossings(position: i32, amount: i32) -> i32 {
+        // Turning left is the same as turning right on a mirrored dial.
+        let (position, amount) = if amount >= 0 {
+            (position, amount)
+        } else {
+            ((SIZE - position) % SIZE, -amount)
         };
-        *clicks += amount / 100;
-        let amount = amount % 100;
-        for _ in 0..amount {
-            self.0 = match self.0 + step {
-                0 | 100 | -100 => {
-                    *clicks += 1;
-                    0
-                }
-                x => x,
-            }
-        }
+        (position + amount) / SIZE
     }
 }
 
 #[aoc(day1, part2)]
 fn part2(input: &[Rotation]) -> usize {
-    let mut dial = Dial(50);
+    let mut dial: Dial = Dial(50);
     let mut clicks = 0usize;
     for &rotation in input {
         dial.rotate_part2(rotation, &mut clicks);
@@ -114,4 +119,54 @@ L82";
     fn part2_r1000() {
         assert_eq!(part2(&[Rotation::R(1000)]), 10);
     }
+
+    /// Turns the dial one click at a time, counting every time it points at zero.
+    fn rotate_clicks<const SIZE: i32>(dial: &mut Dial<SIZE>, rotation: Rotation) -> usize {
+        let amount = rotation.amount();
+        let mut clicks = 0;
+        for _ in 0..amount.abs() {
+            dial.0 = (dial.0 + amount.signum()).rem_euclid(SIZE);
+            if dial.is_zero() {
+                clicks += 1;
+            }
+        }
+        clicks
+    }
+
+    /// A linear congruential generator, so the tests stay reproducible.
+    fn random_numbers(mut seed: u64) -> impl Iterator<Item = i32> {
+        std::iter::repeat_with(move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i32
+        })
+    }
+
+    fn check_against_clicks<const SIZE: i32>(seed: u64) {
+        let mut random = random_numbers(seed);
+        let start = random.next().unwrap() % SIZE;
+        let (mut fast, mut slow) = (Dial::<SIZE>(start), Dial::<SIZE>(start));
+        for _ in 0..1000 {
+            let amount = random.next().unwrap() % (5 * SIZE);
+            let rotation = if random.next().unwrap() % 2 == 0 {
+                Rotation::L(amount)
+            } else {
+                Rotation::R(amount)
+            };
+            let mut clicks = 0;
+            fast.rotate_part2(rotation, &mut clicks);
+            assert_eq!(clicks, rotate_clicks(&mut slow, rotation), "{rotation:?}");
+            assert_eq!(fast.0, slow.0);
+        }
+    }
+
+    #[test]
+    fn part2_random() {
+        for seed in 0..10 {
+            check_against_clicks::<100>(seed);
+            check_against_clicks::<7>(seed);
+            check_against_clicks::<1>(seed);
+        }
+    }
 }
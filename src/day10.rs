@@ -1,3 +1,4 @@
+use crate::util::{Gf2System, IntegerProgram};
 use aoc_runner_derive::{aoc, aoc_generator};
 use bitvec::prelude::*;
 
@@ -5,7 +6,7 @@ use bitvec::prelude::*;
 struct Machine {
     lights: BitVec,
     buttons: Vec<BitVec>,
-    joltages: Vec<u64>,
+    joltages: Vec<i64>,
 }
 
 #[aoc_generator(day10)]
@@ -38,7 +39,7 @@ fn parse(input: &str) -> Vec<Machine> {
                     button_mask
                 })
                 .collect();
-            let joltages: Vec<u64> = joltages
+            let joltages: Vec<i64> = joltages
                 .strip_prefix('{')
                 .unwrap()
                 .strip_suffix('}')
@@ -56,40 +57,10 @@ fn parse(input: &str) -> Vec<Machine> {
 }
 
 fn configure(machine: &Machine) -> BitVec {
-    solve_recurse(
-        machine,
-        &bitvec!(0; machine.lights.len()),
-        BitVec::with_capacity(machine.buttons.len()),
-    )
-    .expect("no solution")
-}
-
-fn solve_recurse(machine: &Machine, lights: &BitVec, presses: BitVec) -> Option<BitVec> {
-    if presses.len() == machine.buttons.len() {
-        return if &machine.lights == lights {
-            Some(presses)
-        } else {
-            None
-        };
-    }
-    let mut presses_off = presses.clone();
-    presses_off.push(false);
-    let mut presses_on = presses.clone();
-    presses_on.push(true);
-    let mut lights_on = lights.clone();
-    lights_on ^= &machine.buttons[presses.len()];
-    let solution_off = solve_recurse(machine, lights, presses_off);
-    let solution_on = solve_recurse(machine, &lights_on, presses_on);
-    match (solution_off, solution_on) {
-        (Some(solution_off), Some(solution_on))
-            if solution_off.count_ones() <= solution_on.count_ones() =>
-        {
-            Some(solution_off)
-        }
-        (_, Some(solution_on)) => Some(solution_on),
-        (Some(solution_off), None) => Some(solution_off),
-        (None, None) => None,
-    }
+    // Each light is toggled by the sum (modulo 2) of the presses of the buttons wired to it.
+    Gf2System::from_columns(&machine.buttons, machine.lights.clone())
+        .min_weight_solution()
+        .expect("no solution")
 }
 
 #[aoc(day10, part1)]
@@ -100,9 +71,40 @@ fn part1(input: &[Machine]) -> usize {
         .sum()
 }
 
+fn configure_joltages(machine: &Machine) -> Vec<i64> {
+    // Each counter must be hit exactly by the buttons that are wired to it.
+    let coefficients = (0..machine.joltages.len())
+        .map(|counter| {
+            machine
+                .buttons
+                .iter()
+                .map(|button| button[counter] as i64)
+                .collect()
+        })
+        .collect();
+    // A button can't be pressed more often than the lowest counter it increments.
+    let upper_bounds = machine
+        .buttons
+        .iter()
+        .map(|button| {
+            button
+                .iter_ones()
+                .map(|counter| machine.joltages[counter])
+                .min()
+                .unwrap_or(0)
+        })
+        .collect();
+    IntegerProgram::new(coefficients, machine.joltages.clone(), upper_bounds)
+        .minimize_sum()
+        .expect("no solution")
+}
+
 #[aoc(day10, part2)]
-fn part2(input: &[Machine]) -> u64 {
-    todo!()
+fn part2(input: &[Machine]) -> i64 {
+    input
+        .iter()
+        .map(|machine| configure_joltages(machine).iter().sum::<i64>())
+        .sum()
 }
 
 #[cfg(test)]
@@ -122,6 +124,10 @@ mod tests {
 
     #[test]
     fn part2_example() {
-        assert_eq!(part2(&parse(EXAMPLE)), 0);
+        let input = parse(EXAMPLE);
+        assert_eq!(configure_joltages(&input[0]).iter().sum::<i64>(), 10);
+        assert_eq!(configure_joltages(&input[1]).iter().sum::<i64>(), 12);
+        assert_eq!(configure_joltages(&input[2]).iter().sum::<i64>(), 11);
+        assert_eq!(part2(&input), 33);
     }
 }
@@ -1,70 +1,248 @@
+use crate::util::{Graph, GraphError, NodeId};
 use aoc_runner_derive::{aoc, aoc_generator};
-use pathfinding::prelude::count_paths;
-use std::collections::HashMap;
+use itertools::Itertools;
 
 #[derive(Debug, Clone)]
 struct Reactor {
-    cables: HashMap<String, Vec<String>>,
+    graph: Graph,
 }
 
 #[aoc_generator(day11)]
 fn parse(input: &str) -> Reactor {
-    let cables = input
-        .lines()
-        .map(|s| {
-            let (input, s) = s.split_once(": ").unwrap();
-            let outputs = s.split(' ').map(str::to_string).collect();
-            (input.to_string(), outputs)
-        })
-        .collect();
-    Reactor { cables }
+    Reactor {
+        graph: Graph::parse(input),
+    }
+}
+
+const YOU: &str = "you";
+const SERVER: &str = "svr";
+const OUT: &str = "out";
+const DAC: &str = "dac";
+const FFT: &str = "fft";
+
+/// The devices reachable from some start device, in topological order.
+struct Reachable {
+    order: Vec<NodeId>,
+    /// The position of every device in `order`, if it's reachable.
+    position: Vec<Option<usize>>,
 }
 
 impl Reactor {
-    fn connections(&self, label: &str) -> impl Iterator<Item = &str> + '_ {
-        self.cables
-            .get(label)
-            .map(Vec::as_slice)
-            .unwrap_or_default()
+    fn node(&self, label: &str) -> Result<NodeId, GraphError> {
+        self.graph
+            .node(label)
+            .ok_or_else(|| GraphError::UnknownLabel(label.to_string()))
+    }
+
+    /// Finds the devices reachable from `start`, checking that none of them lie on a cycle.
+    ///
+    /// Also checks that no device on a path to `end` has an output that doesn't list
+    /// any outputs of its own (other than `end` itself), since that's probably a typo
+    /// that loses paths. This deliberately ignores devices that can't reach `end`:
+    /// when `end` is a device in the middle, everything past it looks like a dead end,
+    /// and such dead ends can't change the number of paths anyway.
+    fn reachable(&self, start: NodeId, end: NodeId) -> Result<Reachable, GraphError> {
+        let order = self.graph.topological_sort_from([start])?;
+        let mut position = vec![None; self.graph.len()];
+        for (i, &node) in order.iter().enumerate() {
+            position[node.index()] = Some(i);
+        }
+        let mut reaches_end = vec![false; self.graph.len()];
+        for &node in order.iter().rev() {
+            if node == end {
+                reaches_end[node.index()] = true;
+                continue;
+            }
+            let outputs = self.graph.successors(node);
+            reaches_end[node.index()] = outputs.iter().any(|next| reaches_end[next.index()]);
+            if !reaches_end[node.index()] {
+                continue;
+            }
+            let dangling = outputs
+                .iter()
+                .find(|&&next| next != end && !self.graph.is_declared(next));
+            if let Some(&to) = dangling {
+                return Err(GraphError::Dangling {
+                    from: self.graph.label(node).to_string(),
+                    to: self.graph.label(to).to_string(),
+                });
+            }
+        }
+        Ok(Reachable { order, position })
+    }
+
+    /// Counts the paths from `start` to `end` that visit every device in `required`
+    /// (in any order) and none of the devices in `forbidden`.
+    fn count_paths(
+        &self,
+        start: &str,
+        end: &str,
+        required: &[&str],
+        forbidden: &[&str],
+    ) -> Result<u128, GraphError> {
+        let start = self.node(start)?;
+        let end = self.node(end)?;
+        let required = required
+            .iter()
+            .map(|&label| self.node(label))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut forbidden_nodes = vec![false; self.graph.len()];
+        for &label in forbidden {
+            forbidden_nodes[self.node(label)?.index()] = true;
+        }
+        let reachable = self.reachable(start, end)?;
+        // Every stop along the way must be reachable, or there are no paths at all.
+        let Some(mut stops) = required
             .iter()
-            .map(String::as_str)
+            .chain([&end])
+            .map(|node| reachable.position[node.index()])
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Ok(0);
+        };
+        // Since there are no cycles, every path visits the required devices in topological order.
+        stops[..required.len()].sort_unstable();
+        Ok([0]
+            .iter()
+            .chain(&stops)
+            .tuple_windows()
+            .map(|(&first, &last)| {
+                reachable.count_segment(&self.graph, first, last, &forbidden_nodes)
+            })
+            .product())
+    }
+
+    /// Indexes the paths from `start` to `end`, see [`PathIndex`].
+    #[allow(dead_code)]
+    fn path_index(&self, start: &str, end: &str) -> Result<PathIndex<'_>, GraphError> {
+        let (start, end) = (self.node(start)?, self.node(end)?);
+        let reachable = self.reachable(start, end)?;
+        let mut index = PathIndex {
+            graph: &self.graph,
+            start,
+            end,
+            counts: vec![0; self.graph.len()],
+            outputs: vec![Vec::new(); self.graph.len()],
+        };
+        for &node in reachable.order.iter().rev() {
+            if node == end {
+                index.counts[node.index()] = 1;
+                continue;
+            }
+            let mut outputs = self
+                .graph
+                .successors(node)
+                .iter()
+                .copied()
+                .filter(|next| index.counts[next.index()] > 0)
+                .collect::<Vec<_>>();
+            outputs.sort_by_key(|&next| self.graph.label(next));
+            index.counts[node.index()] =
+                outputs.iter().map(|next| index.counts[next.index()]).sum();
+            index.outputs[node.index()] = outputs;
+        }
+        Ok(index)
     }
 }
 
-#[aoc(day11, part1)]
-fn part1(input: &Reactor) -> usize {
-    count_paths(
-        "you",
-        |&label| input.connections(label),
-        |&label| label == "out",
-    )
+impl Reachable {
+    /// Counts the paths between the devices at two positions that avoid the forbidden devices.
+    fn count_segment(&self, graph: &Graph, first: usize, last: usize, forbidden: &[bool]) -> u128 {
+        if first > last {
+            return 0;
+        }
+        let mut paths = vec![0u128; graph.len()];
+        paths[self.order[first].index()] = 1;
+        for &node in &self.order[first..last] {
+            let count = paths[node.index()];
+            if count == 0 || forbidden[node.index()] {
+                continue;
+            }
+            for &next in graph.successors(node) {
+                paths[next.index()] += count;
+            }
+        }
+        let to = self.order[last];
+        if forbidden[to.index()] {
+            0
+        } else {
+            paths[to.index()]
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct State<'a> {
-    label: &'a str,
-    dac: bool,
-    fft: bool,
+/// The paths from one device to another, ranked in lexicographic order of their labels.
+///
+/// Knowing how many paths lead to the end from every device,
+/// the path with any given rank can be found without enumerating the ones before it.
+struct PathIndex<'a> {
+    graph: &'a Graph,
+    start: NodeId,
+    end: NodeId,
+    /// The number of paths from every device to the end.
+    counts: Vec<u128>,
+    /// The outputs of every device that lead to the end, sorted by label.
+    outputs: Vec<Vec<NodeId>>,
+}
+
+#[allow(dead_code)]
+impl<'a> PathIndex<'a> {
+    /// The number of paths.
+    fn len(&self) -> u128 {
+        self.counts[self.start.index()]
+    }
+
+    /// The labels along the path with the given rank, starting from 0.
+    fn path(&self, mut rank: u128) -> Option<Vec<&'a str>> {
+        if rank >= self.len() {
+            return None;
+        }
+        let mut node = self.start;
+        let mut path = vec![self.graph.label(node)];
+        while node != self.end {
+            for &next in &self.outputs[node.index()] {
+                let count = self.counts[next.index()];
+                if rank < count {
+                    node = next;
+                    break;
+                }
+                rank -= count;
+            }
+            path.push(self.graph.label(node));
+        }
+        Some(path)
+    }
+
+    /// All paths, in lexicographic order.
+    fn paths(&self) -> impl Iterator<Item = Vec<&'a str>> + '_ {
+        (0..self.len()).map(|rank| self.path(rank).unwrap())
+    }
+
+    /// Picks a path uniformly at random, using `random` as a source of random bits.
+    fn sample(&self, mut random: impl FnMut() -> u64) -> Option<Vec<&'a str>> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        // Reject the lowest values, so that the remaining ones are spread evenly over all ranks.
+        let threshold = len.wrapping_neg() % len;
+        loop {
+            let value = (u128::from(random()) << 64) | u128::from(random());
+            if value >= threshold {
+                return self.path(value % len);
+            }
+        }
+    }
+}
+
+#[aoc(day11, part1)]
+fn part1(input: &Reactor) -> u128 {
+    input.count_paths(YOU, OUT, &[], &[]).unwrap()
 }
 
 #[aoc(day11, part2)]
-fn part2(input: &Reactor) -> usize {
-    count_paths(
-        State {
-            label: "svr",
-            dac: false,
-            fft: false,
-        },
-        |state| {
-            let (dac, fft) = (state.dac, state.fft);
-            input.connections(state.label).map(move |next_label| State {
-                label: next_label,
-                dac: next_label == "dac" || dac,
-                fft: next_label == "fft" || fft,
-            })
-        },
-        |state| state.label == "out" && state.dac && state.fft,
-    )
+fn part2(input: &Reactor) -> u128 {
+    input.count_paths(SERVER, OUT, &[DAC, FFT], &[]).unwrap()
 }
 
 #[cfg(test)]
@@ -83,4 +261,108 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE2)), 2);
     }
+
+    #[test]
+    fn test_count_paths() {
+        let reactor = parse(EXAMPLE2);
+        let count = |required, forbidden| reactor.count_paths(SERVER, OUT, required, forbidden);
+        assert_eq!(count(&[], &[]), Ok(8));
+        assert_eq!(count(&["hub"], &[]), Ok(4));
+        assert_eq!(count(&[DAC, FFT], &["hhh"]), Ok(1));
+        assert_eq!(count(&["hub", DAC], &[]), Ok(0));
+        assert_eq!(count(&[], &["ccc"]), Ok(0));
+        assert_eq!(reactor.count_paths("ccc", "fff", &[], &[]), Ok(2));
+        assert_eq!(reactor.count_paths(SERVER, FFT, &[], &[]), Ok(1));
+        assert_eq!(reactor.count_paths("ccc", OUT, &[], &[]), Ok(4));
+        assert_eq!(reactor.count_paths("fff", OUT, &["ccc"], &[]), Ok(0));
+    }
+
+    #[test]
+    fn test_invalid_reactors() {
+        let reactor = parse("you: aaa\naaa: bbb\nbbb: ccc out\nccc: aaa\n");
+        let error = reactor.count_paths(YOU, OUT, &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "cycle: aaa -> bbb -> ccc -> aaa");
+        // Cycles that can't be reached from the start don't matter.
+        let reactor = parse("you: out\naaa: bbb\nbbb: aaa\n");
+        assert_eq!(reactor.count_paths(YOU, OUT, &[], &[]), Ok(1));
+        // Dead ends that don't branch off a path to the end don't matter.
+        let reactor = parse("you: aaa bbb\naaa: out\nbbb: zzz\n");
+        assert_eq!(reactor.count_paths(YOU, OUT, &[], &[]), Ok(1));
+        let reactor = parse("you: aaa bbb\naaa: out\n");
+        let error = reactor.count_paths(YOU, "oot", &[], &[]).unwrap_err();
+        assert_eq!(error.to_string(), "unknown label oot");
+        assert_eq!(
+            reactor.count_paths(YOU, OUT, &[], &[]),
+            Err(GraphError::Dangling {
+                from: YOU.to_string(),
+                to: "bbb".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_path_index() {
+        let reactor = parse(EXAMPLE1);
+        let index = reactor.path_index(YOU, OUT).unwrap();
+        assert_eq!(index.len(), 5);
+        let paths = index.paths().collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                vec!["you", "bbb", "ddd", "ggg", "out"],
+                vec!["you", "bbb", "eee", "out"],
+                vec!["you", "ccc", "ddd", "ggg", "out"],
+                vec!["you", "ccc", "eee", "out"],
+                vec!["you", "ccc", "fff", "out"],
+            ]
+        );
+        assert_eq!(index.path(5), None);
+        assert_eq!(reactor.path_index("out", YOU).unwrap().len(), 0);
+        assert!(matches!(
+            reactor.path_index("zzz", OUT),
+            Err(GraphError::UnknownLabel(label)) if label == "zzz"
+        ));
+    }
+
+    #[test]
+    fn test_path_index_matches_counts() {
+        let reactor = parse(EXAMPLE2);
+        let index = reactor.path_index(SERVER, OUT).unwrap();
+        let paths = index.paths().collect::<Vec<_>>();
+        assert_eq!(paths.len() as u128, index.len());
+        assert!(paths.is_sorted());
+        let to_dac = reactor.path_index(SERVER, DAC).unwrap();
+        assert_eq!(
+            to_dac.paths().collect::<Vec<_>>(),
+            [
+                vec!["svr", "aaa", "fft", "ccc", "eee", "dac"],
+                vec!["svr", "bbb", "tty", "ccc", "eee", "dac"],
+            ]
+        );
+        let through_both = paths
+            .iter()
+            .filter(|path| path.contains(&DAC) && path.contains(&FFT))
+            .count();
+        assert_eq!(through_both as u128, part2(&reactor));
+    }
+
+    #[test]
+    fn test_sample() {
+        let reactor = parse(EXAMPLE1);
+        let index = reactor.path_index(YOU, OUT).unwrap();
+        let mut seed = 42u64;
+        let mut random = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            seed
+        };
+        let mut seen = vec![0; 5];
+        for _ in 0..500 {
+            let path = index.sample(&mut random).unwrap();
+            let rank = index.paths().position(|other| other == path).unwrap();
+            seen[rank] += 1;
+        }
+        assert!(seen.iter().all(|&count| count > 50), "{seen:?}");
+    }
 }
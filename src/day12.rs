@@ -1,8 +1,5 @@
-use crate::util::Vector2D;
+use crate::util::{ExactCover, Grid, Polyomino, Vector2D};
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashSet;
-
-type Shape = HashSet<Vector2D>;
 
 #[derive(Debug, Clone)]
 struct Region {
@@ -13,7 +10,7 @@ struct Region {
 
 #[derive(Debug, Clone)]
 struct Input {
-    shapes: Vec<Shape>,
+    shapes: Vec<Polyomino>,
     regions: Vec<Region>,
 }
 
@@ -24,19 +21,13 @@ fn parse(input: &str) -> Input {
     let shapes = shapes
         .iter()
         .map(|shape| {
-            let mut lines = shape.lines();
-            let _name = lines.next().unwrap();
-            let mut shape = HashSet::new();
-            for (y, line) in lines.enumerate() {
-                for (x, c) in line.chars().enumerate() {
-                    match c {
-                        '#' => shape.insert(Vector2D::new(x as i32, y as i32)),
-                        '.' => continue,
-                        c => panic!("invalid character {c}"),
-                    };
-                }
-            }
-            shape
+            let (_name, shape) = shape.split_once('\n').unwrap();
+            let shape = Grid::parse(shape, |c| match c {
+                '#' => true,
+                '.' => false,
+                c => panic!("invalid character {c}"),
+            });
+            Polyomino::new(shape.positions_where(|&filled| filled))
         })
         .collect();
     let regions = regions
@@ -54,29 +45,38 @@ fn parse(input: &str) -> Input {
     Input { shapes, regions }
 }
 
-const PRESENT_SIZE: usize = 3;
-
 impl Input {
     fn can_fit(&self, region: &Region) -> bool {
-        // Very naive!
         if self.can_fit_without_interlocking(region) {
             true
         } else if !self.can_fit_with_ideal_interlocking(region) {
             false
         } else {
-            // This case doesn't actually happen on the real input. ¯\_(ツ)_/¯
-            panic!("this shouldn't happen")
+            // Ambiguous: actually try to pack the presents.
+            self.pack(region).is_some()
         }
     }
 
     fn can_fit_without_interlocking(&self, region: &Region) -> bool {
-        // All presents fit in a 3x3 square.
-        // If the region is large enough to hold as many 3x3 squares as there are presents,
+        // All presents fit in a box as wide as the widest present and as high as the highest one.
+        // If the region is large enough to hold as many of those boxes as there are presents,
         // then their shape doesn't matter: they'll fit regardless.
-        let region_area = region.width * region.height;
-        let maximum_presents_area =
-            region.presents.iter().copied().sum::<usize>() * (PRESENT_SIZE * PRESENT_SIZE);
-        region_area >= maximum_presents_area
+        let num_presents = region.presents.iter().copied().sum::<usize>();
+        if num_presents == 0 {
+            return true;
+        }
+        let shapes = self.shapes_in(region).map(|(shape, _)| &self.shapes[shape]);
+        let (box_width, box_height) = shapes.fold((0, 0), |(width, height), shape| {
+            (width.max(shape.width()), height.max(shape.height()))
+        });
+        // Rotating every present turns the box on its side.
+        [(box_width, box_height), (box_height, box_width)]
+            .into_iter()
+            .any(|(box_width, box_height)| {
+                let num_boxes =
+                    (region.width / box_width as usize) * (region.height / box_height as usize);
+                num_boxes >= num_presents
+            })
     }
 
     fn can_fit_with_ideal_interlocking(&self, region: &Region) -> bool {
@@ -84,7 +84,21 @@ impl Input {
         // as the number of filled squares occupied by each present,
         // assuming they all interlock perfectly without leaving any gaps.
         let region_area = region.width * region.height;
-        let minimum_presents_area = region
+        region_area >= self.presents_area(region)
+    }
+
+    /// The shapes of the presents in the region, with how many of each.
+    fn shapes_in<'a>(&self, region: &'a Region) -> impl Iterator<Item = (usize, usize)> + 'a {
+        region
+            .presents
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(shape, &count)| (shape, count))
+    }
+
+    fn presents_area(&self, region: &Region) -> usize {
+        region
             .presents
             .iter()
             .copied()
@@ -93,8 +107,71 @@ impl Input {
                 let filled_squares_for_shape = self.shapes[idx].len();
                 filled_squares_for_shape * count
             })
-            .sum::<usize>();
-        region_area >= minimum_presents_area
+            .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Placement {
+    shape: usize,
+    cells: Vec<Vector2D>,
+}
+
+impl Input {
+    /// All the ways a single present can be placed in the region.
+    fn candidate_placements(&self, region: &Region, shape: usize) -> Vec<Vec<Vector2D>> {
+        let mut candidates = Vec::new();
+        for orientation in self.shapes[shape].orientations() {
+            for y in 0..=(region.height as i32 - orientation.height()) {
+                for x in 0..=(region.width as i32 - orientation.width()) {
+                    let offset = Vector2D::new(x, y);
+                    let cells = orientation.cells().iter();
+                    candidates.push(cells.map(|&pos| pos + offset).collect());
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Packs the presents as an exact cover problem. Every present must be placed,
+    /// and every cell must be filled either by a present or by one of the leftover holes.
+    fn pack(&self, region: &Region) -> Option<Vec<Placement>> {
+        if !self.can_fit_with_ideal_interlocking(region) {
+            return None;
+        }
+        let shapes = self.shapes_in(region).collect::<Vec<_>>();
+        let num_cells = region.width * region.height;
+        let num_holes = num_cells - self.presents_area(region);
+        // Columns: one per shape, one per cell, and one shared by all holes.
+        let cell_column =
+            |pos: Vector2D| shapes.len() + (pos.y() as usize) * region.width + (pos.x() as usize);
+        let hole_column = shapes.len() + num_cells;
+        let mut cover = ExactCover::new(hole_column + usize::from(num_holes > 0), 0);
+        let mut placements = Vec::new();
+        for (column, &(shape, count)) in shapes.iter().enumerate() {
+            cover.set_multiplicity(column, count);
+            for cells in self.candidate_placements(region, shape) {
+                let columns = std::iter::once(column)
+                    .chain(cells.iter().copied().map(cell_column))
+                    .collect::<Vec<_>>();
+                cover.add_row(&columns);
+                placements.push(Some(Placement { shape, cells }));
+            }
+        }
+        if num_holes > 0 {
+            cover.set_multiplicity(hole_column, num_holes);
+            for cell in 0..num_cells {
+                cover.add_row(&[shapes.len() + cell, hole_column]);
+                placements.push(None);
+            }
+        }
+        let solution = cover.first_solution()?;
+        Some(
+            solution
+                .into_iter()
+                .filter_map(|row| placements[row].clone())
+                .collect(),
+        )
     }
 }
 
@@ -108,19 +185,49 @@ fn part1(input: &Input) -> usize {
 }
 
 #[aoc(day12, part2)]
-fn part2(input: &Input) -> usize {
+fn part2(_input: &Input) -> usize {
     todo!()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
 
     static EXAMPLE: &str = include_str!("../example/2025/day12.txt");
 
-    #[ignore]
     #[test]
     fn part1_example() {
         assert_eq!(part1(&parse(EXAMPLE)), 2);
     }
+
+    #[test]
+    fn test_orientations() {
+        let input = parse(EXAMPLE);
+        assert_eq!(input.shapes[0].orientations().len(), 8);
+        assert_eq!(input.shapes[4].orientations().len(), 4);
+        assert_eq!(input.shapes[5].orientations().len(), 2);
+    }
+
+    #[test]
+    fn test_pack() {
+        let input = parse(EXAMPLE);
+        for region in &input.regions[0..2] {
+            let placements = input.pack(region).unwrap();
+            assert_eq!(
+                placements.len(),
+                region.presents.iter().copied().sum::<usize>()
+            );
+            let cells = placements
+                .iter()
+                .flat_map(|placement| placement.cells.iter().copied())
+                .collect::<HashSet<_>>();
+            assert_eq!(cells.len(), input.presents_area(region));
+            assert!(cells.iter().all(|pos| {
+                (0..region.width as i32).contains(&pos.x())
+                    && (0..region.height as i32).contains(&pos.y())
+            }));
+        }
+        assert_eq!(input.pack(&input.regions[2]), None);
+    }
 }
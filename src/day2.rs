@@ -1,5 +1,6 @@
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Itertools;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 
 struct Range {
     start: u64,
@@ -20,57 +21,306 @@ fn parse(input: &str) -> Vec<Range> {
         .collect()
 }
 
-fn to_digits(mut n: u64) -> Vec<u8> {
-    let mut digits = Vec::new();
-    while n > 0 {
-        digits.push((n % 10) as u8);
-        n /= 10;
+/// How the chunks of an invalid ID relate to each other.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Chunks {
+    /// Every chunk is the same, like `123123123`.
+    Exact,
+    /// Every chunk is the reverse of the one before it, like `123321123`.
+    Palindromic,
+}
+
+/// Which IDs are invalid: those made of a chunk of digits repeated
+/// between `min_repeats` and `max_repeats` times (inclusive), written in base `radix`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidRule {
+    radix: u64,
+    min_repeats: u32,
+    max_repeats: u32,
+    chunks: Chunks,
+}
+
+/// Why an [`InvalidRule`] can't be created.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InvalidRuleError {
+    /// Numbers need at least two different digits.
+    RadixTooSmall(u64),
+    /// A chunk must be repeated at least twice, or every ID would be invalid.
+    TooFewRepeats(u32),
+    /// The minimum number of repeats is larger than the maximum.
+    EmptyRepeats { min: u32, max: u32 },
+}
+
+impl Display for InvalidRuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidRuleError::RadixTooSmall(radix) => {
+                write!(f, "radix must be at least 2, got {radix}")
+            }
+            InvalidRuleError::TooFewRepeats(min) => {
+                write!(f, "chunks must repeat at least twice, got {min}")
+            }
+            InvalidRuleError::EmptyRepeats { min, max } => {
+                write!(f, "minimum repeats {min} exceeds maximum repeats {max}")
+            }
+        }
     }
-    // No need to reverse: invalid IDs will still be invalid when reversed.
-    digits
 }
 
-fn is_invalid_part1(id: u64) -> bool {
-    let digits = to_digits(id);
-    if !digits.len().is_multiple_of(2) {
-        return false;
+impl std::error::Error for InvalidRuleError {}
+
+impl InvalidRule {
+    pub fn new(
+        radix: u64,
+        min_repeats: u32,
+        max_repeats: u32,
+        chunks: Chunks,
+    ) -> Result<Self, InvalidRuleError> {
+        if radix < 2 {
+            return Err(InvalidRuleError::RadixTooSmall(radix));
+        }
+        if min_repeats < 2 {
+            return Err(InvalidRuleError::TooFewRepeats(min_repeats));
+        }
+        if min_repeats > max_repeats {
+            return Err(InvalidRuleError::EmptyRepeats {
+                min: min_repeats,
+                max: max_repeats,
+            });
+        }
+        Ok(Self {
+            radix,
+            min_repeats,
+            max_repeats,
+            chunks,
+        })
+    }
+
+    pub fn radix(&self) -> u64 {
+        self.radix
+    }
+
+    pub fn min_repeats(&self) -> u32 {
+        self.min_repeats
+    }
+
+    pub fn max_repeats(&self) -> u32 {
+        self.max_repeats
+    }
+
+    pub fn chunks(&self) -> Chunks {
+        self.chunks
+    }
+
+    /// A chunk of decimal digits repeated exactly twice.
+    pub const PART1: Self = Self {
+        radix: 10,
+        min_repeats: 2,
+        max_repeats: 2,
+        chunks: Chunks::Exact,
+    };
+
+    /// A chunk of decimal digits repeated at least twice.
+    pub const PART2: Self = Self {
+        radix: 10,
+        min_repeats: 2,
+        max_repeats: u32::MAX,
+        chunks: Chunks::Exact,
+    };
+
+    /// The digits of a number, most significant first.
+    fn digits(&self, mut n: u64) -> Vec<u64> {
+        let mut digits = Vec::new();
+        while n > 0 {
+            digits.push(n % self.radix);
+            n /= self.radix;
+        }
+        digits.reverse();
+        digits
+    }
+
+    fn num_digits(&self, n: u64) -> u32 {
+        n.checked_ilog(self.radix).map_or(1, |log| log + 1)
+    }
+
+    /// Whether an ID with `len` digits may be split into chunks of `chunk_len` digits.
+    fn allows_chunk_len(&self, len: u32, chunk_len: u32) -> bool {
+        len.is_multiple_of(chunk_len)
+            && (self.min_repeats..=self.max_repeats).contains(&(len / chunk_len))
+    }
+
+    /// Checks a single ID by looking at its digits.
+    pub fn is_invalid(&self, id: u64) -> bool {
+        let digits = self.digits(id);
+        let len = digits.len() as u32;
+        (1..=len)
+            .filter(|&chunk_len| self.allows_chunk_len(len, chunk_len))
+            .any(|chunk_len| {
+                let chunks = digits.chunks_exact(chunk_len as usize).collect::<Vec<_>>();
+                chunks.windows(2).all(|pair| match self.chunks {
+                    Chunks::Exact => pair[0] == pair[1],
+                    Chunks::Palindromic => pair[0].iter().eq(pair[1].iter().rev()),
+                })
+            })
+    }
+
+    /// Sum of all invalid IDs in a range.
+    fn sum_invalid(&self, range: &Range) -> u64 {
+        let lengths = self.num_digits(range.start)..=self.num_digits(range.end);
+        let sum = match self.chunks {
+            Chunks::Exact => lengths.map(|len| self.sum_exact(range, len)).sum::<u128>(),
+            Chunks::Palindromic => lengths.map(|len| self.sum_palindromic(range, len)).sum(),
+        };
+        sum.try_into().unwrap()
+    }
+
+    /// Sum of all IDs in the range with `len` digits
+    /// that consist of a chunk of `chunk_len` digits repeated over and over.
+    fn sum_repeated(&self, range: &Range, len: u32, chunk_len: u32) -> u128 {
+        let radix = u128::from(self.radix);
+        // Repeating a chunk `c` gives `c * 100..0100..01`, with a one every `chunk_len` digits.
+        let multiplier = (radix.pow(len) - 1) / (radix.pow(chunk_len) - 1);
+        let first_chunk = radix
+            .pow(chunk_len - 1)
+            .max(u128::from(range.start).div_ceil(multiplier));
+        let last_chunk = (radix.pow(chunk_len) - 1).min(u128::from(range.end) / multiplier);
+        if first_chunk > last_chunk {
+            return 0;
+        }
+        let sum_chunks = (first_chunk + last_chunk) * (last_chunk - first_chunk + 1) / 2;
+        multiplier * sum_chunks
+    }
+
+    /// Sum of all invalid IDs in the range with `len` digits, for exactly repeated chunks.
+    fn sum_exact(&self, range: &Range, len: u32) -> u128 {
+        // An ID repeats a chunk of length `k` if and only if its shortest repeating chunk divides `k`.
+        // Use Möbius inversion to find the sum of the IDs whose shortest chunk has each length `d`,
+        // then add up those for which some allowed chunk length is a multiple of `d`.
+        let divisors = divisors(len);
+        let mut total = 0i128;
+        for &d in &divisors {
+            if !divisors
+                .iter()
+                .any(|&k| k.is_multiple_of(d) && self.allows_chunk_len(len, k))
+            {
+                continue;
+            }
+            for &e in divisors.iter().filter(|&&e| d.is_multiple_of(e)) {
+                total += mobius(d / e) as i128 * self.sum_repeated(range, len, e) as i128;
+            }
+        }
+        total.try_into().unwrap()
+    }
+
+    /// Sum of all invalid IDs in the range with `len` digits, for palindromic chunks.
+    fn sum_palindromic(&self, range: &Range, len: u32) -> u128 {
+        // There's no neat closed form here, so build every candidate from its first chunk.
+        let radix = u128::from(self.radix);
+        let (start, end) = (u128::from(range.start), u128::from(range.end));
+        let mut ids = HashSet::new();
+        for chunk_len in (1..=len).filter(|&chunk_len| self.allows_chunk_len(len, chunk_len)) {
+            // The first chunk holds the most significant digits.
+            let shift = radix.pow(len - chunk_len);
+            let first_chunk = radix.pow(chunk_len - 1).max(start / shift);
+            let last_chunk = (radix.pow(chunk_len) - 1).min(end / shift);
+            for chunk in first_chunk..=last_chunk {
+                let digits = self.digits(chunk as u64);
+                let mut id = 0u128;
+                for i in 0..len / chunk_len {
+                    let mut chunk_digits = digits.clone();
+                    if i % 2 == 1 {
+                        chunk_digits.reverse();
+                    }
+                    for digit in chunk_digits {
+                        id = id * radix + u128::from(digit);
+                    }
+                }
+                if (start..=end).contains(&id) {
+                    ids.insert(id);
+                }
+            }
+        }
+        ids.into_iter().sum()
     }
-    let half_len = digits.len() / 2;
-    digits[0..half_len] == digits[half_len..]
 }
 
-#[aoc(day2, part1)]
-fn part1(input: &[Range]) -> u64 {
-    input
-        .iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| is_invalid_part1(id))
-        .sum()
+fn divisors(n: u32) -> Vec<u32> {
+    (1..=n).filter(|&d| n.is_multiple_of(d)).collect()
 }
 
-fn is_invalid_part2(id: u64) -> bool {
-    let digits = to_digits(id);
-    for chunk_size in 1..=(digits.len() / 2) {
-        let mut chunks = digits.as_slice().chunks_exact(chunk_size);
-        if chunks.remainder().is_empty() && chunks.all_equal() {
-            return true;
+fn mobius(mut n: u32) -> i32 {
+    let mut result = 1;
+    let mut p = 2;
+    while p * p <= n {
+        if n.is_multiple_of(p) {
+            n /= p;
+            if n.is_multiple_of(p) {
+                return 0;
+            }
+            result = -result;
         }
+        p += 1;
+    }
+    if n > 1 {
+        result = -result;
     }
-    false
+    result
+}
+
+fn sum_invalid(input: &[Range], rule: &InvalidRule) -> u64 {
+    input.iter().map(|range| rule.sum_invalid(range)).sum()
+}
+
+/// Sums the IDs that are invalid according to `rule`,
+/// in a list of ranges formatted like the puzzle input.
+pub fn sum_invalid_ids(input: &str, rule: &InvalidRule) -> u64 {
+    sum_invalid(&parse(input), rule)
+}
+
+#[aoc(day2, part1)]
+fn part1(input: &[Range]) -> u64 {
+    sum_invalid(input, &InvalidRule::PART1)
 }
 
 #[aoc(day2, part2)]
 fn part2(input: &[Range]) -> u64 {
-    input
-        .iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| is_invalid_part2(id))
-        .sum()
+    sum_invalid(input, &InvalidRule::PART2)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
+
+    fn to_digits(mut n: u64) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while n > 0 {
+            digits.push((n % 10) as u8);
+            n /= 10;
+        }
+        // No need to reverse: invalid IDs will still be invalid when reversed.
+        digits
+    }
+
+    fn is_invalid_part1(id: u64) -> bool {
+        let digits = to_digits(id);
+        if !digits.len().is_multiple_of(2) {
+            return false;
+        }
+        let half_len = digits.len() / 2;
+        digits[0..half_len] == digits[half_len..]
+    }
+
+    fn is_invalid_part2(id: u64) -> bool {
+        let digits = to_digits(id);
+        for chunk_size in 1..=(digits.len() / 2) {
+            let mut chunks = digits.as_slice().chunks_exact(chunk_size);
+            if chunks.remainder().is_empty() && chunks.all_equal() {
+                return true;
+            }
+        }
+        false
+    }
 
     static EXAMPLE: &str = r"11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
 
@@ -106,4 +356,86 @@ mod tests {
         assert!(!is_invalid_part1(56));
         assert!(!is_invalid_part1(112211));
     }
+
+    #[test]
+    fn test_sum_invalid_ids() {
+        assert_eq!(sum_invalid_ids(EXAMPLE, &InvalidRule::PART1), 1227775554);
+        let rule = InvalidRule::new(10, 2, 3, Chunks::Exact).unwrap();
+        let expected = parse(EXAMPLE)
+            .iter()
+            .flat_map(|range| range.start..=range.end)
+            .filter(|&id| rule.is_invalid(id))
+            .sum::<u64>();
+        assert_eq!(sum_invalid_ids(EXAMPLE, &rule), expected);
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let ranges = [
+            (1, 10_000),
+            (95, 115),
+            (99_990, 1_000_100),
+            (1_188_511_000, 1_188_512_000),
+        ];
+        for (start, end) in ranges {
+            let range = Range { start, end };
+            let part1 = (start..=end)
+                .filter(|&id| is_invalid_part1(id))
+                .sum::<u64>();
+            let part2 = (start..=end)
+                .filter(|&id| is_invalid_part2(id))
+                .sum::<u64>();
+            assert_eq!(
+                InvalidRule::PART1.sum_invalid(&range),
+                part1,
+                "{start}-{end}"
+            );
+            assert_eq!(
+                InvalidRule::PART2.sum_invalid(&range),
+                part2,
+                "{start}-{end}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_other_rules() {
+        let rules = [
+            InvalidRule::new(2, 3, u32::MAX, Chunks::Exact).unwrap(),
+            InvalidRule::new(10, 2, 3, Chunks::Exact).unwrap(),
+            InvalidRule::new(10, 2, u32::MAX, Chunks::Palindromic).unwrap(),
+            InvalidRule::new(16, 2, 2, Chunks::Palindromic).unwrap(),
+        ];
+        assert!(rules[2].is_invalid(123321123));
+        assert!(!rules[2].is_invalid(123123));
+        for rule in rules {
+            for (start, end) in [(1, 10_000), (99_990, 130_000)] {
+                let range = Range { start, end };
+                let expected = (start..=end).filter(|&id| rule.is_invalid(id)).sum::<u64>();
+                assert_eq!(rule.sum_invalid(&range), expected, "{rule:?} {start}-{end}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_new_rule() {
+        assert_eq!(
+            InvalidRule::new(10, 2, 2, Chunks::Exact),
+            Ok(InvalidRule::PART1)
+        );
+        assert_eq!(
+            InvalidRule::new(1, 2, 2, Chunks::Exact),
+            Err(InvalidRuleError::RadixTooSmall(1))
+        );
+        assert_eq!(
+            InvalidRule::new(10, 1, 2, Chunks::Exact),
+            Err(InvalidRuleError::TooFewRepeats(1))
+        );
+        let error = InvalidRule::new(10, 3, 2, Chunks::Palindromic).unwrap_err();
+        assert_eq!(error, InvalidRuleError::EmptyRepeats { min: 3, max: 2 });
+        assert_eq!(
+            error.to_string(),
+            "minimum repeats 3 exceeds maximum repeats 2"
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use aoc_runner_derive::{aoc, aoc_generator};
+use num_bigint::BigUint;
 
 type Bank = Vec<u8>;
 
@@ -13,21 +14,53 @@ fn parse(input: &str) -> Vec<Bank> {
     input.lines().map(parse_bank).collect()
 }
 
-fn max_joltage(bank: &Bank, num_batteries: usize) -> u64 {
-    let mut joltage = 0;
-    let mut start_idx = 0usize;
-    for num_battery in (0..num_batteries).rev() {
-        let (idx, battery) = bank[..(bank.len() - num_battery)]
-            .iter()
-            .copied()
-            .enumerate()
-            .skip(start_idx)
-            .max_by(|(i1, b1), (i2, b2)| b1.cmp(b2).then_with(|| i2.cmp(i1)))
-            .unwrap();
-        joltage += (battery as u64) * 10u64.pow(num_battery as u32);
-        start_idx = idx + 1;
+/// Picks `num_batteries` batteries that form the largest possible joltage,
+/// without changing their order. Returns the positions of the chosen batteries.
+fn max_joltage_positions(bank: &[u8], num_batteries: usize) -> Vec<usize> {
+    assert!(num_batteries <= bank.len(), "not enough batteries");
+    // Keep a stack of chosen batteries. A larger battery replaces all smaller ones before it,
+    // as long as there are enough batteries left to fill up the rest.
+    let mut chosen = Vec::<usize>::with_capacity(num_batteries);
+    for (idx, &battery) in bank.iter().enumerate() {
+        let remaining = bank.len() - idx;
+        while let Some(&top) = chosen.last()
+            && bank[top] < battery
+            && chosen.len() - 1 + remaining >= num_batteries
+        {
+            chosen.pop();
+        }
+        if chosen.len() < num_batteries {
+            chosen.push(idx);
+        }
     }
-    joltage
+    chosen
+}
+
+/// The largest joltage as a string of digits.
+#[allow(dead_code)]
+fn max_joltage_digits(bank: &[u8], num_batteries: usize) -> String {
+    max_joltage_positions(bank, num_batteries)
+        .into_iter()
+        .map(|idx| char::from(b'0' + bank[idx]))
+        .collect()
+}
+
+/// The largest joltage, for any number of batteries.
+#[allow(dead_code)]
+fn max_joltage_big(bank: &[u8], num_batteries: usize) -> BigUint {
+    max_joltage_positions(bank, num_batteries)
+        .into_iter()
+        .fold(BigUint::ZERO, |joltage, idx| joltage * 10u8 + bank[idx])
+}
+
+/// The largest joltage, if it fits in a `u64`.
+fn max_joltage(bank: &[u8], num_batteries: usize) -> u64 {
+    max_joltage_positions(bank, num_batteries)
+        .into_iter()
+        .try_fold(0u64, |joltage, idx| {
+            joltage.checked_mul(10)?.checked_add(bank[idx] as u64)
+        })
+        .expect("joltage too large")
 }
 
 #[aoc(day3, part1)]
@@ -86,4 +119,22 @@ mod tests {
             888911112111
         );
     }
+
+    #[test]
+    fn test_max_joltage_positions() {
+        let bank = parse_bank("818181911112111");
+        assert_eq!(max_joltage_positions(&bank, 2), vec![6, 11]);
+        assert_eq!(
+            max_joltage_positions(&bank, 15),
+            (0..15).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_max_joltage_large() {
+        let bank = parse_bank(&"9876543210".repeat(5));
+        let digits = max_joltage_digits(&bank, 30);
+        assert_eq!(digits, "999876543298765432109876543210");
+        assert_eq!(max_joltage_big(&bank, 30), digits.parse().unwrap());
+    }
 }
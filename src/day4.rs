@@ -1,57 +1,63 @@
-use crate::util::Vector2D;
+use crate::util::Grid;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashSet;
-
-type Grid = HashSet<Vector2D>;
+use std::collections::VecDeque;
 
 #[aoc_generator(day4)]
-fn parse(input: &str) -> Grid {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars().enumerate().filter_map(move |(x, c)| match c {
-                '@' => Some(Vector2D::new(x as i32, y as i32)),
-                '.' => None,
-                c => panic!("invalid character: {c}"),
-            })
-        })
-        .collect()
+fn parse(input: &str) -> Grid<bool> {
+    Grid::parse(input, |c| match c {
+        '@' => true,
+        '.' => false,
+        c => panic!("invalid character: {c}"),
+    })
 }
 
-fn removable_rolls(grid: &Grid) -> Vec<Vector2D> {
-    grid.iter()
-        .copied()
-        .filter(|&roll| {
-            let neighbour_rolls = roll
-                .neighbours_diagonal()
-                .filter(|pos| grid.contains(pos))
-                .count();
-            neighbour_rolls < 4
-        })
-        .collect()
+/// The wave in which each roll gets removed, or `None` for rolls that never get removed.
+///
+/// Rolls with fewer than 4 neighbouring rolls are removed in wave 0.
+/// Every later wave removes the rolls that drop below 4 neighbours once the previous wave is gone.
+fn removal_waves(grid: &Grid<bool>) -> Grid<Option<usize>> {
+    let mut neighbour_rolls = grid.map(|_| 0usize);
+    let mut waves = grid.map(|_| None);
+    let mut queue = VecDeque::new();
+    for roll in grid.positions_where(|&roll| roll) {
+        neighbour_rolls[roll] = grid
+            .neighbours_diagonal(roll)
+            .filter(|&pos| grid[pos])
+            .count();
+        if neighbour_rolls[roll] < 4 {
+            waves[roll] = Some(0);
+            queue.push_back(roll);
+        }
+    }
+    // The queue holds rolls in order of their wave,
+    // so a roll's neighbours are only updated once all earlier waves are gone.
+    while let Some(roll) = queue.pop_front() {
+        let wave = waves[roll].unwrap();
+        for neighbour in grid.neighbours_diagonal(roll) {
+            if grid[neighbour] && waves[neighbour].is_none() {
+                neighbour_rolls[neighbour] -= 1;
+                if neighbour_rolls[neighbour] < 4 {
+                    waves[neighbour] = Some(wave + 1);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+    waves
 }
 
 #[aoc(day4, part1)]
-fn part1(grid: &Grid) -> usize {
-    removable_rolls(grid).len()
+fn part1(grid: &Grid<bool>) -> usize {
+    removal_waves(grid)
+        .positions_where(|&wave| wave == Some(0))
+        .count()
 }
 
 #[aoc(day4, part2)]
-fn part2(grid: &Grid) -> usize {
-    let mut grid = grid.clone();
-    let mut total_removed = 0usize;
-    loop {
-        let removable = removable_rolls(&grid);
-        if removable.is_empty() {
-            break;
-        }
-        total_removed += removable.len();
-        for roll in removable {
-            grid.remove(&roll);
-        }
-    }
-    total_removed
+fn part2(grid: &Grid<bool>) -> usize {
+    removal_waves(grid)
+        .positions_where(|wave| wave.is_some())
+        .count()
 }
 
 #[cfg(test)]
@@ -78,4 +84,19 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 43);
     }
+
+    #[test]
+    fn test_removal_waves() {
+        let waves = removal_waves(&parse(EXAMPLE));
+        let mut wave_sizes = Vec::new();
+        for (_, &wave) in waves.cells() {
+            if let Some(wave) = wave {
+                if wave_sizes.len() <= wave {
+                    wave_sizes.resize(wave + 1, 0);
+                }
+                wave_sizes[wave] += 1;
+            }
+        }
+        assert_eq!(wave_sizes, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+    }
 }
@@ -1,9 +1,9 @@
+use crate::util::RangeSet;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::ops::RangeInclusive;
 
 #[derive(Debug, Clone)]
 struct Inventory {
-    fresh_ranges: Vec<RangeInclusive<u64>>,
+    fresh_ranges: RangeSet<u64>,
     ingredients: Vec<u64>,
 }
 
@@ -27,69 +27,18 @@ fn parse(input: &str) -> Inventory {
     }
 }
 
-impl Inventory {
-    fn is_fresh(&self, ingredient: u64) -> bool {
-        self.fresh_ranges
-            .iter()
-            .any(|range| range.contains(&ingredient))
-    }
-}
-
 #[aoc(day5, part1)]
 fn part1(inventory: &Inventory) -> usize {
     inventory
         .ingredients
         .iter()
-        .filter(|&&ingredient| inventory.is_fresh(ingredient))
+        .filter(|&&ingredient| inventory.fresh_ranges.contains(ingredient))
         .count()
 }
 
-impl Inventory {
-    fn normalize_ranges(&mut self) {
-        // Inspired by TimeRanges::Normalize() from Mozilla Firefox
-        // https://searchfox.org/firefox-main/rev/39bc83bb8632d54d70542dc5d98c046a317ec99d/dom/media/mediaelement/TimeRanges.cpp#107
-        if self.fresh_ranges.len() < 2 {
-            return;
-        }
-        // Sort ranges
-        self.fresh_ranges.sort_by(|left, right| {
-            left.start()
-                .cmp(right.start())
-                .then_with(|| left.end().cmp(right.end()))
-        });
-        // Merge consecutive ranges
-        let mut ranges = Vec::with_capacity(self.fresh_ranges.len());
-        let mut current_range = self.fresh_ranges[0].clone();
-        for range in &self.fresh_ranges[1..] {
-            if current_range.start() <= range.start() && range.end() <= current_range.end() {
-                // New range is fully contained in current range, skip it.
-                continue;
-            } else if current_range.end() >= range.start() {
-                // Consecutive or overlapping range.
-                current_range = (*current_range.start())..=(*range.end());
-            } else {
-                // Separate range.
-                ranges.push(current_range);
-                current_range = range.clone();
-            }
-        }
-        ranges.push(current_range);
-        self.fresh_ranges = ranges;
-    }
-
-    fn count_fresh(&self) -> usize {
-        self.fresh_ranges
-            .iter()
-            .map(|range| range.clone().count())
-            .sum()
-    }
-}
-
 #[aoc(day5, part2)]
 fn part2(inventory: &Inventory) -> usize {
-    let mut inventory = inventory.clone();
-    inventory.normalize_ranges();
-    inventory.count_fresh()
+    inventory.fresh_ranges.len() as usize
 }
 
 #[cfg(test)]
@@ -1,97 +1,211 @@
 use aoc_runner_derive::aoc;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum Op {
-    #[default]
     Add,
+    Subtract,
     Multiply,
+    Min,
+    Max,
+    /// Writes the operands next to each other, like `12 | 34 = 1234`.
+    Concat,
 }
 
-#[derive(Debug, Default, Clone)]
-struct Problem {
-    operands: Vec<u64>,
-    op: Op,
-}
+impl Op {
+    fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            '+' => Op::Add,
+            '-' => Op::Subtract,
+            '*' => Op::Multiply,
+            '<' => Op::Min,
+            '>' => Op::Max,
+            '|' => Op::Concat,
+            _ => return None,
+        })
+    }
 
-fn parse_part1(input: &str) -> Vec<Problem> {
-    let mut problems = Vec::new();
-    for line in input.lines() {
-        for (i, s) in line.split_ascii_whitespace().enumerate() {
-            if problems.get(i).is_none() {
-                problems.push(Problem::default());
-            }
-            let problem = problems.get_mut(i).unwrap();
-            match s {
-                "+" => problem.op = Op::Add,
-                "*" => problem.op = Op::Multiply,
-                s => problem.operands.push(s.parse().unwrap()),
-            }
-        }
+    fn apply(self, operands: &[i64]) -> i64 {
+        let (&first, rest) = operands.split_first().expect("no operands");
+        rest.iter().fold(first, |acc, &x| match self {
+            Op::Add => acc + x,
+            Op::Subtract => acc - x,
+            Op::Multiply => acc * x,
+            Op::Min => acc.min(x),
+            Op::Max => acc.max(x),
+            Op::Concat => acc * 10i64.pow(x.checked_ilog10().unwrap_or(0) + 1) + x,
+        })
     }
-    problems
 }
 
-impl Problem {
-    fn solve(&self) -> u64 {
-        match self.op {
-            Op::Add => self.operands.iter().sum(),
-            Op::Multiply => self.operands.iter().product(),
-        }
+/// How to read the numbers of a problem from its digits.
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Reading {
+    /// Every row holds one number.
+    Rows,
+    /// Every column holds one number, written top to bottom.
+    /// The first number is in the leftmost column.
+    ColumnsLeftToRight,
+    /// Every column holds one number, written top to bottom.
+    /// The first number is in the rightmost column. This is how cephalopods do math.
+    ColumnsRightToLeft,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ParseError {
+    /// 1-based line number.
+    line: usize,
+    /// 1-based column number.
+    column: usize,
+    message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
     }
 }
 
-#[aoc(day6, part1)]
-fn part1(input: &str) -> u64 {
-    let problems = parse_part1(input);
-    problems.iter().map(|p| p.solve()).sum()
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone)]
+struct Problem {
+    /// The columns spanned by this problem.
+    columns: Range<usize>,
+    op: Op,
+}
+
+/// A worksheet with all digits in their original columns.
+#[derive(Debug, Clone)]
+struct Worksheet {
+    /// The rows of digits, padded with spaces so they all have the same width.
+    rows: Vec<Vec<char>>,
+    problems: Vec<Problem>,
 }
 
-fn parse_part2(input: &str) -> Vec<Problem> {
-    let mut line_iters = input
-        .lines()
-        .map(|line| line.chars().rev())
-        .collect::<Vec<_>>();
-    let mut problems = Vec::new();
-    let mut problem = Problem::default();
-    'outer: loop {
-        let column = line_iters
-            .iter_mut()
-            .filter_map(|i| i.next())
+impl Worksheet {
+    fn parse(input: &str) -> Result<Self, ParseError> {
+        // Lines without any content don't belong to any problem.
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| (idx + 1, line.chars().collect::<Vec<_>>()))
             .collect::<Vec<_>>();
-        if column.is_empty() {
-            // End of input
-            break;
+        let Some((op_line, op_row)) = lines.pop() else {
+            return Err(ParseError {
+                line: 1,
+                column: 1,
+                message: "empty worksheet".to_string(),
+            });
+        };
+        let width = lines
+            .iter()
+            .map(|(_, row)| row.len())
+            .chain([op_row.len()])
+            .max()
+            .unwrap();
+        let mut rows = Vec::with_capacity(lines.len());
+        for (line, mut row) in lines {
+            if let Some(column) = row.iter().position(|&c| c != ' ' && !c.is_ascii_digit()) {
+                return Err(ParseError {
+                    line,
+                    column: column + 1,
+                    message: format!("expected a digit, found {:?}", row[column]),
+                });
+            }
+            row.resize(width, ' ');
+            rows.push(row);
         }
-        if column.iter().all(|&c| c == ' ') {
-            // Blank column between problems
-            continue;
+        let is_blank = |x: usize| {
+            op_row.get(x).is_none_or(|&c| c == ' ') && rows.iter().all(|row| row[x] == ' ')
+        };
+        // Problems are separated by columns that are blank from top to bottom.
+        let mut problems = Vec::new();
+        let mut x = 0;
+        while x < width {
+            if is_blank(x) {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < width && !is_blank(x) {
+                x += 1;
+            }
+            let columns = start..x;
+            let mut ops = columns
+                .clone()
+                .filter(|&x| op_row.get(x).is_some_and(|&c| c != ' '));
+            let (Some(op_column), None) = (ops.next(), ops.next()) else {
+                return Err(ParseError {
+                    line: op_line,
+                    column: start + 1,
+                    message: "expected exactly one operator per problem".to_string(),
+                });
+            };
+            let op = Op::from_char(op_row[op_column]).ok_or_else(|| ParseError {
+                line: op_line,
+                column: op_column + 1,
+                message: format!("unknown operator {:?}", op_row[op_column]),
+            })?;
+            problems.push(Problem { columns, op });
         }
-        let mut num = 0u64;
-        for c in column {
-            match c {
-                '+' | '*' => {
-                    problem.operands.push(num);
-                    problem.op = if c == '+' { Op::Add } else { Op::Multiply };
-                    problems.push(problem);
-                    problem = Problem::default();
-                    continue 'outer;
-                }
-                c if c.is_ascii_digit() => {
-                    num = num * 10 + (c.to_digit(10).unwrap() as u64);
+        Ok(Self { rows, problems })
+    }
+
+    /// Reads the numbers of a problem, skipping rows or columns without any digits.
+    fn operands(&self, problem: &Problem, reading: Reading) -> Vec<i64> {
+        let parse_digits = |digits: &mut dyn Iterator<Item = char>| {
+            digits
+                .filter_map(|c| c.to_digit(10))
+                .fold(None, |num, digit| {
+                    Some(num.unwrap_or(0) * 10 + digit as i64)
+                })
+        };
+        match reading {
+            Reading::Rows => self
+                .rows
+                .iter()
+                .filter_map(|row| parse_digits(&mut row[problem.columns.clone()].iter().copied()))
+                .collect(),
+            Reading::ColumnsLeftToRight | Reading::ColumnsRightToLeft => {
+                let mut operands = problem
+                    .columns
+                    .clone()
+                    .filter_map(|x| parse_digits(&mut self.rows.iter().map(|row| row[x])))
+                    .collect::<Vec<_>>();
+                if reading == Reading::ColumnsRightToLeft {
+                    operands.reverse();
                 }
-                ' ' => continue,
-                c => panic!("invalid character {c}"),
+                operands
             }
         }
-        problem.operands.push(num);
     }
-    problems
+
+    fn solve(&self, reading: Reading) -> i64 {
+        self.problems
+            .iter()
+            .map(|problem| problem.op.apply(&self.operands(problem, reading)))
+            .sum()
+    }
+}
+
+#[aoc(day6, part1)]
+fn part1(input: &str) -> i64 {
+    Worksheet::parse(input).unwrap().solve(Reading::Rows)
 }
 
 #[aoc(day6, part2)]
-fn part2(input: &str) -> u64 {
-    let problems = parse_part2(input);
-    problems.iter().map(|p| p.solve()).sum()
+fn part2(input: &str) -> i64 {
+    Worksheet::parse(input)
+        .unwrap()
+        .solve(Reading::ColumnsRightToLeft)
 }
 
 #[cfg(test)]
@@ -112,4 +226,42 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 3263827);
     }
+
+    #[test]
+    fn test_more_ops() {
+        let worksheet = Worksheet::parse("12 7 3 4\n 5 9 5 2\n-  < > |").unwrap();
+        let results = worksheet
+            .problems
+            .iter()
+            .map(|problem| {
+                problem
+                    .op
+                    .apply(&worksheet.operands(problem, Reading::Rows))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(results, vec![7, 7, 5, 42]);
+        let first = &worksheet.problems[0];
+        assert_eq!(
+            worksheet.operands(first, Reading::ColumnsLeftToRight),
+            vec![1, 25]
+        );
+        assert_eq!(
+            worksheet.operands(first, Reading::ColumnsRightToLeft),
+            vec![25, 1]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = Worksheet::parse("12 34\n5x 67\n+  *").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+        let error = Worksheet::parse("12 34\n56 78\n+  ?").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 4));
+        let error = Worksheet::parse("12 34\n56 78\n+*  ").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+        assert_eq!(
+            error.to_string(),
+            "line 3, column 1: expected exactly one operator per problem"
+        );
+    }
 }
@@ -1,79 +1,142 @@
-use crate::util::Vector2D;
+use crate::util::{Grid, Vector2D, render_heatmap_text, render_text, write_heatmap_ppm};
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Either;
-use pathfinding::directed::count_paths::count_paths;
-use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
 struct Manifold {
     height: i32,
     start: Vector2D,
-    splitters: HashSet<Vector2D>,
+    splitters: Grid<bool>,
 }
 
 #[aoc_generator(day7)]
 fn parse(input: &str) -> Manifold {
-    let height = input.lines().count() as i32;
-    let mut start = None::<Vector2D>;
-    let mut splitters = HashSet::new();
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.chars().enumerate() {
-            match c {
-                'S' => {
-                    start = Some(Vector2D::new(x as i32, y as i32));
+    let grid = Grid::parse(input, |c| match c {
+        'S' | '^' | '.' => c,
+        c => panic!("invalid character {c}"),
+    });
+    let start = grid.positions_where(|&c| c == 'S').next().unwrap();
+    Manifold {
+        height: grid.height() as i32,
+        start,
+        splitters: grid.map(|&c| c == '^'),
+    }
+}
+
+impl Manifold {
+    fn is_splitter(&self, pos: Vector2D) -> bool {
+        self.splitters.get(pos).copied().unwrap_or(false)
+    }
+}
+
+/// The result of sending a beam through the manifold.
+#[derive(Debug, Clone)]
+struct Simulation {
+    /// How many timelines pass through each cell.
+    /// For a splitter, this is the number of timelines that hit it.
+    timelines: Grid<u64>,
+    /// How many timelines leave the manifold at the bottom.
+    num_timelines: u64,
+}
+
+impl Manifold {
+    /// Sweeps through the manifold row by row,
+    /// keeping track of how many timelines have a beam in each column.
+    fn simulate(&self) -> Simulation {
+        let width = self.splitters.width();
+        let mut timelines = Grid::filled(width, self.splitters.height(), 0u64);
+        // Beams can leave the grid at the sides, so keep an extra column on either side.
+        let mut beams = vec![0u64; width + 2];
+        beams[self.start.x() as usize + 1] = 1;
+        let add_beam = |beams: &mut [u64], timelines: &mut Grid<u64>, pos: Vector2D, count: u64| {
+            beams[(pos.x() + 1) as usize] += count;
+            if let Some(cell) = timelines.get_mut(pos) {
+                *cell += count;
+            }
+        };
+        for y in self.start.y()..self.height {
+            let mut next_beams = vec![0u64; width + 2];
+            for (idx, &count) in beams.iter().enumerate() {
+                if count == 0 {
+                    continue;
                 }
-                '^' => {
-                    splitters.insert(Vector2D::new(x as i32, y as i32));
+                let pos = Vector2D::new(idx as i32 - 1, y);
+                if self.is_splitter(pos) {
+                    timelines[pos] += count;
+                    add_beam(
+                        &mut next_beams,
+                        &mut timelines,
+                        pos + Vector2D::new(-1, 0),
+                        count,
+                    );
+                    add_beam(
+                        &mut next_beams,
+                        &mut timelines,
+                        pos + Vector2D::new(1, 0),
+                        count,
+                    );
+                } else {
+                    add_beam(&mut next_beams, &mut timelines, pos, count);
                 }
-                '.' => continue,
-                c => panic!("invalid character {c}"),
             }
+            beams = next_beams;
+        }
+        Simulation {
+            timelines,
+            num_timelines: beams.iter().sum(),
         }
     }
-    Manifold {
-        height,
-        start: start.unwrap(),
-        splitters,
+}
+
+impl Simulation {
+    /// How many timelines hit each splitter that was hit at least once.
+    fn splitter_hits<'a>(
+        &'a self,
+        manifold: &'a Manifold,
+    ) -> impl Iterator<Item = (Vector2D, u64)> + 'a {
+        manifold
+            .splitters
+            .positions_where(|&splitter| splitter)
+            .map(|pos| (pos, self.timelines[pos]))
+            .filter(|&(_, hits)| hits > 0)
     }
 }
 
-#[aoc(day7, part1)]
-fn part1(manifold: &Manifold) -> usize {
-    let mut num_splits = 0;
-    let mut beams = HashSet::<i32>::new();
-    let mut y = manifold.start.y();
-    beams.insert(manifold.start.x());
-    while y < manifold.height {
-        let mut next_beams = HashSet::<i32>::new();
-        for beam in beams {
-            let pos = Vector2D::new(beam, y);
-            if manifold.splitters.contains(&pos) {
-                num_splits += 1;
-                next_beams.insert(pos.x() - 1);
-                next_beams.insert(pos.x() + 1);
+#[allow(dead_code)]
+impl Simulation {
+    /// Draws the manifold with the beams in it, like the puzzle's own illustrations.
+    fn render(&self, manifold: &Manifold) -> String {
+        render_text(&self.timelines, |pos, &count| {
+            if pos == manifold.start {
+                'S'
+            } else if manifold.splitters[pos] {
+                '^'
+            } else if count > 0 {
+                '|'
             } else {
-                next_beams.insert(pos.x());
+                '.'
             }
-        }
-        beams = next_beams;
-        y += 1;
+        })
+    }
+
+    /// Draws how many timelines pass through each cell as a text heatmap.
+    fn render_heatmap(&self) -> String {
+        render_heatmap_text(&self.timelines)
+    }
+
+    /// Writes how many timelines pass through each cell as a PPM image.
+    fn write_heatmap_ppm(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        write_heatmap_ppm(&self.timelines, out)
     }
-    num_splits
+}
+
+#[aoc(day7, part1)]
+fn part1(manifold: &Manifold) -> usize {
+    manifold.simulate().splitter_hits(manifold).count()
 }
 
 #[aoc(day7, part2)]
-fn part2(manifold: &Manifold) -> usize {
-    count_paths(
-        manifold.start,
-        |&pos| {
-            if manifold.splitters.contains(&pos) {
-                Either::Left([pos + Vector2D::new(-1, 0), pos + Vector2D::new(1, 0)].into_iter())
-            } else {
-                Either::Right([pos + Vector2D::new(0, 1)].into_iter())
-            }
-        },
-        |pos| pos.y() == manifold.height,
-    )
+fn part2(manifold: &Manifold) -> u64 {
+    manifold.simulate().num_timelines
 }
 
 #[cfg(test)]
@@ -91,4 +154,51 @@ mod tests {
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 40);
     }
+
+    #[test]
+    fn test_splitter_hits() {
+        let manifold = parse(EXAMPLE);
+        let simulation = manifold.simulate();
+        let hits = simulation.splitter_hits(&manifold).collect::<Vec<_>>();
+        // The first splitter is hit once, right below the start.
+        assert_eq!(hits[0], (manifold.start + Vector2D::new(0, 2), 1));
+        // Every hit turns one timeline into two.
+        let total_hits = hits.iter().map(|&(_, hits)| hits).sum::<u64>();
+        assert_eq!(simulation.num_timelines, 1 + total_hits);
+    }
+
+    #[test]
+    fn test_tall_manifold() {
+        let mut input = String::from(".S.\n");
+        for _ in 0..5000 {
+            input += ".^.\n...\n";
+        }
+        let manifold = parse(&input);
+        let simulation = manifold.simulate();
+        assert_eq!(simulation.splitter_hits(&manifold).count(), 1);
+        assert_eq!(simulation.num_timelines, 2);
+    }
+
+    #[test]
+    fn test_render() {
+        let manifold = parse(EXAMPLE);
+        let expected = r".......S.......
+.......|.......
+......|^|......
+......|.|......
+.....|^|^|.....
+.....|.|.|.....
+....|^|^|^|....
+....|.|.|.|....
+...|^|^|||^|...
+...|.|.|||.|...
+..|^|^|||^|^|..
+..|.|.|||.|.|..
+.|^|||^||.||^|.
+.|.|||.||.||.|.
+|^|^|^|^|^|||^|
+|.|.|.|.|.|||.|
+";
+        assert_eq!(manifold.simulate().render(&manifold), expected);
+    }
 }
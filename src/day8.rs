@@ -1,8 +1,5 @@
-use crate::util::Vector3D;
+use crate::util::{KdTree, Metric, UnionFind, Vector3D};
 use aoc_runner_derive::{aoc, aoc_generator};
-use disjoint::DisjointSet;
-use itertools::Itertools;
-use std::cmp::Reverse;
 
 #[aoc_generator(day8)]
 fn parse(input: &str) -> Vec<Vector3D<i64>> {
@@ -18,27 +15,50 @@ fn parse(input: &str) -> Vec<Vector3D<i64>> {
         .collect()
 }
 
-fn get_pairs(
-    boxes: &[Vector3D<i64>],
-) -> impl Iterator<Item = ((usize, Vector3D<i64>), (usize, Vector3D<i64>))> + '_ {
-    boxes
-        .iter()
-        .copied()
-        .enumerate()
-        .tuple_combinations::<(_, _)>()
-        .sorted_by_key(|((_, a), (_, b))| a.euclidean_distance_squared(b))
+/// A connection between two junction boxes, by their index.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Edge {
+    first: usize,
+    second: usize,
+    distance_squared: i64,
+}
+
+impl Edge {
+    /// The order in which the closest pairs of boxes get connected: ties are broken by index.
+    fn key(&self) -> (i64, usize, usize) {
+        (self.distance_squared, self.first, self.second)
+    }
+}
+
+/// Finds a minimum spanning tree, with its edges in the order in which they join two circuits
+/// when connecting the closest pairs of boxes one by one.
+fn minimum_spanning_tree(tree: &KdTree<3, i64>) -> Vec<Edge> {
+    tree.minimum_spanning_tree(Metric::Euclidean)
+        .into_iter()
+        .map(|(first, second, distance_squared)| Edge {
+            first,
+            second,
+            distance_squared,
+        })
+        .collect()
 }
 
 fn connect(boxes: &[Vector3D<i64>], num_connections: usize) -> usize {
-    let mut links = DisjointSet::with_len(boxes.len());
-    let pairs = get_pairs(boxes).take(num_connections);
-    for ((i, _), (j, _)) in pairs {
-        links.join(i, j);
+    let tree = KdTree::new(boxes);
+    let last = tree
+        .pairs(Metric::Euclidean)
+        .take(num_connections)
+        .last()
+        .map(|(first, second, distance_squared)| (distance_squared, first, second));
+    // Connecting two boxes that are already in the same circuit doesn't change anything,
+    // so only the edges of the spanning tree matter.
+    let mut circuits = UnionFind::new(boxes.len());
+    for edge in minimum_spanning_tree(&tree) {
+        if last.is_some_and(|last| edge.key() <= last) {
+            circuits.union(edge.first, edge.second);
+        }
     }
-    let mut sets = links.sets();
-    sets.sort_by_key(|set| Reverse(set.len()));
-    let three_largest_sets = &sets[0..3];
-    three_largest_sets.iter().map(|set| set.len()).product()
+    circuits.largest_components(3).iter().product()
 }
 
 #[aoc(day8, part1)]
@@ -47,15 +67,10 @@ fn part1(boxes: &[Vector3D<i64>]) -> usize {
 }
 
 fn connect_until_single(boxes: &[Vector3D<i64>]) -> (Vector3D<i64>, Vector3D<i64>) {
-    let mut links = DisjointSet::with_len(boxes.len());
-    let pairs = get_pairs(boxes);
-    for ((i, box1), (j, box2)) in pairs {
-        links.join(i, j);
-        if (0..boxes.len()).map(|k| links.root_of(k)).all_equal() {
-            return (box1, box2);
-        }
-    }
-    panic!("no single circuit after all pairs are connected");
+    let last_edge = *minimum_spanning_tree(&KdTree::new(boxes))
+        .last()
+        .expect("need at least two boxes");
+    (boxes[last_edge.first], boxes[last_edge.second])
 }
 
 #[aoc(day8, part2)]
@@ -82,4 +97,44 @@ mod tests {
             (Vector3D::new(216, 146, 977), Vector3D::new(117, 168, 530))
         );
     }
+
+    #[test]
+    fn test_minimum_spanning_tree() {
+        let boxes = parse(EXAMPLE);
+        let tree = KdTree::new(&boxes);
+        let edges = minimum_spanning_tree(&tree);
+        assert_eq!(edges.len(), boxes.len() - 1);
+        assert!(edges.windows(2).all(|pair| pair[0].key() < pair[1].key()));
+        // The closest pair is always part of the tree.
+        let closest = tree.pairs(Metric::Euclidean).next().unwrap();
+        assert_eq!(
+            edges[0],
+            Edge {
+                first: closest.0,
+                second: closest.1,
+                distance_squared: closest.2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_many_boxes_with_outliers() {
+        // A dense cloud of boxes, plus a few far away that only join at the very end.
+        let mut state = 1u64;
+        let mut random = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            ((state >> 33) % 100_000) as i64
+        };
+        let mut boxes = (0..100_000)
+            .map(|_| Vector3D::new(random(), random(), random()))
+            .collect::<Vec<_>>();
+        let outlier = Vector3D::new(10_000_000, 0, 0);
+        boxes.push(Vector3D::new(-5_000_000, 0, 0));
+        boxes.push(outlier);
+        let tree = KdTree::new(&boxes);
+        let edges = minimum_spanning_tree(&tree);
+        assert_eq!(edges.len(), boxes.len() - 1);
+        assert!(edges.windows(2).all(|pair| pair[0].key() < pair[1].key()));
+        assert_eq!(boxes[edges.last().unwrap().second], outlier);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::util::Vector2D;
+use crate::util::{Polygon, Vector2D};
 use aoc_runner_derive::{aoc, aoc_generator};
 use itertools::Itertools;
 
@@ -44,46 +44,16 @@ fn part1(input: &[Vector2D<i64>]) -> i64 {
         .unwrap()
 }
 
-impl Rect {
-    fn intersects_line(&self, first: Vector2D<i64>, second: Vector2D<i64>) -> bool {
-        let start = first.min(second);
-        let end = first.max(second);
-        if start.x() == end.x() {
-            ((self.top_left.x() + 1)..self.bottom_right.x()).contains(&start.x())
-                && (self.top_left.y() + 1) <= end.y()
-                && start.y() < self.bottom_right.y()
-        } else {
-            assert_eq!(start.y(), end.y());
-            ((self.top_left.y() + 1)..self.bottom_right.y()).contains(&start.y())
-                && (self.top_left.x() + 1) <= end.x()
-                && start.x() < self.bottom_right.x()
-        }
-    }
-}
-
 #[aoc(day9, part2)]
 fn part2(input: &[Vector2D<i64>]) -> i64 {
-    // The real input is a large circle with a thin horizontal cut-out in the middle.
-    // The largest rectangle will always share a corner with this cut-out.
-    let cutout_index = input
-        .iter()
-        .tuple_windows()
-        .position(|(&x, &y)| (x - y).manhattan_distance() > 90_000)
-        .unwrap();
-    let corners = &input[cutout_index + 1..][0..2];
-    let best_rect = input
+    let tiles = Polygon::new(input.to_vec()).tile_map();
+    input
         .iter()
-        .cartesian_product(corners)
-        .map(|(&first, &second)| Rect::new(first, second))
-        .filter(|rect| {
-            input
-                .iter()
-                .tuple_windows()
-                .all(|(&first, &second)| !rect.intersects_line(first, second))
-        })
-        .max_by_key(Rect::area)
-        .unwrap();
-    best_rect.area()
+        .tuple_combinations::<(_, _)>()
+        .filter(|&(&first, &second)| tiles.contains_rectangle(first, second))
+        .map(|(&first, &second)| Rect::new(first, second).area())
+        .max()
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -96,10 +66,22 @@ mod tests {
         assert_eq!(part1(&parse(EXAMPLE)), 50);
     }
 
-    // Does not work on example input.
-    #[ignore]
     #[test]
     fn part2_example() {
         assert_eq!(part2(&parse(EXAMPLE)), 24);
     }
+
+    #[test]
+    fn part2_adjacent_edges() {
+        // The two edges around the notch are next to each other, so every tile is red or green.
+        let input = parse("0,0\n1,0\n1,5\n2,5\n2,0\n10,0\n10,10\n0,10");
+        assert_eq!(part2(&input), 121);
+    }
+
+    #[test]
+    fn test_num_tiles() {
+        let polygon = Polygon::new(parse(EXAMPLE));
+        assert_eq!(polygon.boundary_points() + polygon.interior_points(), 46);
+        assert_eq!(polygon.tile_map().num_tiles(), 46);
+    }
 }
@@ -12,6 +12,9 @@ mod day8;
 mod day9;
 mod util;
 
+pub use day2::{Chunks, InvalidRule, InvalidRuleError, sum_invalid_ids};
+pub use util::{FillRule, Polygon, Segment, TileMap};
+
 extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
@@ -0,0 +1,221 @@
+use super::Vector2D;
+
+/// A compressed grid for a sparse set of points.
+///
+/// Every distinct x and y coordinate gets its own column and row,
+/// and every gap between two consecutive coordinates is squashed into a single column or row.
+/// There's also a one-wide border around everything, so the outside is connected.
+#[derive(Debug, Clone)]
+pub struct CompressedGrid {
+    xs: Vec<i64>,
+    ys: Vec<i64>,
+}
+
+/// The result of a flood fill on a [`CompressedGrid`].
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct FloodFill {
+    /// Whether each cell was reached, indexed by [`CompressedGrid::index`].
+    pub filled: Vec<bool>,
+    /// The total real area of all reached cells.
+    pub area: i64,
+}
+
+#[allow(dead_code)]
+impl CompressedGrid {
+    pub fn new(points: impl IntoIterator<Item = Vector2D<i64>>) -> Self {
+        let (mut xs, mut ys): (Vec<_>, Vec<_>) =
+            points.into_iter().map(|pos| (pos.x(), pos.y())).unzip();
+        xs.sort();
+        xs.dedup();
+        ys.sort();
+        ys.dedup();
+        assert!(!xs.is_empty(), "no points to compress");
+        Self { xs, ys }
+    }
+
+    /// Number of compressed columns.
+    pub fn width(&self) -> usize {
+        2 * self.xs.len() + 1
+    }
+
+    /// Number of compressed rows.
+    pub fn height(&self) -> usize {
+        2 * self.ys.len() + 1
+    }
+
+    pub fn in_bounds(&self, cell: Vector2D) -> bool {
+        (0..self.width() as i32).contains(&cell.x())
+            && (0..self.height() as i32).contains(&cell.y())
+    }
+
+    pub fn index(&self, cell: Vector2D) -> usize {
+        debug_assert!(self.in_bounds(cell));
+        (cell.y() as usize) * self.width() + (cell.x() as usize)
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Vector2D> + '_ {
+        (0..self.height() as i32)
+            .flat_map(move |y| (0..self.width() as i32).map(move |x| Vector2D::new(x, y)))
+    }
+
+    /// Maps one of the original points to its compressed cell.
+    ///
+    /// Returns `None` if either coordinate isn't one of the original ones.
+    pub fn compress(&self, pos: Vector2D<i64>) -> Option<Vector2D> {
+        let x = self.xs.binary_search(&pos.x()).ok()?;
+        let y = self.ys.binary_search(&pos.y()).ok()?;
+        Some(Vector2D::new(2 * x as i32 + 1, 2 * y as i32 + 1))
+    }
+
+    /// Maps any point to the compressed cell that contains it,
+    /// or `None` if it lies beyond the border.
+    pub fn cell_of(&self, pos: Vector2D<i64>) -> Option<Vector2D> {
+        let x = compress_coord(&self.xs, pos.x())?;
+        let y = compress_coord(&self.ys, pos.y())?;
+        Some(Vector2D::new(x as i32, y as i32))
+    }
+
+    /// The real point at the top left corner of a compressed cell.
+    pub fn decompress(&self, cell: Vector2D) -> Vector2D<i64> {
+        Vector2D::new(
+            decompress_coord(&self.xs, cell.x() as usize),
+            decompress_coord(&self.ys, cell.y() as usize),
+        )
+    }
+
+    /// The real width of a compressed column.
+    pub fn column_width(&self, x: usize) -> i64 {
+        coord_size(&self.xs, x)
+    }
+
+    /// The real height of a compressed row.
+    pub fn row_height(&self, y: usize) -> i64 {
+        coord_size(&self.ys, y)
+    }
+
+    /// The real area of a compressed cell.
+    pub fn cell_area(&self, cell: Vector2D) -> i64 {
+        self.column_width(cell.x() as usize) * self.row_height(cell.y() as usize)
+    }
+
+    /// Fills all cells reachable from `start` through horizontal or vertical steps,
+    /// only entering cells for which `can_enter` returns true.
+    pub fn flood_fill(
+        &self,
+        start: Vector2D,
+        mut can_enter: impl FnMut(Vector2D) -> bool,
+    ) -> FloodFill {
+        let mut filled = vec![false; self.width() * self.height()];
+        let mut area = 0;
+        if !can_enter(start) {
+            return FloodFill { filled, area };
+        }
+        filled[self.index(start)] = true;
+        let mut stack = vec![start];
+        while let Some(cell) = stack.pop() {
+            area += self.cell_area(cell);
+            for neighbour in cell.neighbours() {
+                if self.in_bounds(neighbour)
+                    && !filled[self.index(neighbour)]
+                    && can_enter(neighbour)
+                {
+                    filled[self.index(neighbour)] = true;
+                    stack.push(neighbour);
+                }
+            }
+        }
+        FloodFill { filled, area }
+    }
+}
+
+fn compress_coord(coords: &[i64], value: i64) -> Option<usize> {
+    match coords.binary_search(&value) {
+        Ok(i) => Some(2 * i + 1),
+        Err(0) if value < coords[0] - 1 => None,
+        Err(i) if i == coords.len() && value > coords[i - 1] + 1 => None,
+        Err(i) => Some(2 * i),
+    }
+}
+
+fn decompress_coord(coords: &[i64], index: usize) -> i64 {
+    if index == 0 {
+        coords[0] - 1
+    } else {
+        // Odd indices are the coordinates themselves, even ones start right after them.
+        coords[(index - 1) / 2] + ((index - 1) % 2) as i64
+    }
+}
+
+fn coord_size(coords: &[i64], index: usize) -> i64 {
+    if index % 2 == 1 || index == 0 || index == 2 * coords.len() {
+        // A coordinate, or the border.
+        1
+    } else {
+        coords[index / 2] - coords[index / 2 - 1] - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> CompressedGrid {
+        CompressedGrid::new([
+            Vector2D::new(10, 100),
+            Vector2D::new(15, 100),
+            Vector2D::new(15, 101),
+            Vector2D::new(10, 101),
+        ])
+    }
+
+    #[test]
+    fn test_compress() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (5, 5));
+        assert_eq!(
+            grid.compress(Vector2D::new(15, 100)),
+            Some(Vector2D::new(3, 1))
+        );
+        assert_eq!(grid.compress(Vector2D::new(12, 100)), None);
+        assert_eq!(
+            grid.cell_of(Vector2D::new(12, 100)),
+            Some(Vector2D::new(2, 1))
+        );
+        assert_eq!(
+            grid.cell_of(Vector2D::new(9, 102)),
+            Some(Vector2D::new(0, 4))
+        );
+        assert_eq!(grid.cell_of(Vector2D::new(8, 100)), None);
+        for cell in grid.cells() {
+            let pos = grid.decompress(cell);
+            if grid.cell_area(cell) > 0 {
+                assert_eq!(grid.cell_of(pos), Some(cell));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sizes() {
+        let grid = example();
+        let widths = (0..grid.width())
+            .map(|x| grid.column_width(x))
+            .collect::<Vec<_>>();
+        assert_eq!(widths, vec![1, 1, 4, 1, 1]);
+        let heights = (0..grid.height())
+            .map(|y| grid.row_height(y))
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![1, 1, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let grid = example();
+        // Everything except the middle columns of the middle rows.
+        let fill = grid.flood_fill(Vector2D::new(0, 0), |cell| {
+            !(1..=3).contains(&cell.x()) || !(1..=3).contains(&cell.y())
+        });
+        assert_eq!(fill.area, 8 * 4 - 6 * 2);
+        assert!(!fill.filled[grid.index(Vector2D::new(2, 1))]);
+    }
+}
@@ -0,0 +1,449 @@
+use std::ops::ControlFlow;
+
+/// An exact cover problem, solved with Knuth's Dancing Links (Algorithm X).
+///
+/// Every primary column must be covered by exactly one chosen row,
+/// or by exactly as many rows as its multiplicity.
+/// Secondary columns may be covered by at most one chosen row.
+#[derive(Debug, Clone)]
+pub struct ExactCover {
+    num_primary: usize,
+    num_columns: usize,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+    /// How many more times each column must be covered.
+    bound: Vec<usize>,
+    num_rows: usize,
+}
+
+/// The root node, which links together all uncovered primary columns.
+const ROOT: usize = 0;
+
+#[allow(dead_code)]
+impl ExactCover {
+    pub fn new(num_primary: usize, num_secondary: usize) -> Self {
+        let num_columns = num_primary + num_secondary;
+        // Node 0 is the root, nodes 1..=num_columns are the column headers.
+        let num_headers = num_columns + 1;
+        let mut left = (0..num_headers).collect::<Vec<_>>();
+        let mut right = (0..num_headers).collect::<Vec<_>>();
+        for c in 0..=num_primary {
+            left[c] = if c == 0 { num_primary } else { c - 1 };
+            right[c] = if c == num_primary { 0 } else { c + 1 };
+        }
+        // Secondary columns link to themselves, so they're never chosen for branching.
+        Self {
+            num_primary,
+            num_columns,
+            left,
+            right,
+            up: (0..num_headers).collect(),
+            down: (0..num_headers).collect(),
+            column: (0..num_headers).collect(),
+            row: vec![usize::MAX; num_headers],
+            size: vec![0; num_headers],
+            bound: vec![1; num_headers],
+            num_rows: 0,
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// Requires a primary column to be covered by exactly `count` rows instead of just one.
+    ///
+    /// This is much faster than adding `count` interchangeable columns,
+    /// since the rows of such a column are only ever chosen in the order they were added.
+    pub fn set_multiplicity(&mut self, column: usize, count: usize) {
+        assert!(
+            column < self.num_primary,
+            "only primary columns have a multiplicity"
+        );
+        assert!(count > 0, "multiplicity must be positive");
+        self.bound[column + 1] = count;
+    }
+
+    /// Adds a row covering the given columns, and returns its index.
+    ///
+    /// Primary columns are numbered first, followed by the secondary columns.
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        let row = self.num_rows;
+        self.num_rows += 1;
+        let first = self.left.len();
+        for (i, &column) in columns.iter().enumerate() {
+            assert!(column < self.num_columns, "column out of range");
+            let header = column + 1;
+            let node = self.left.len();
+            self.left.push(if i == 0 { node } else { node - 1 });
+            self.right.push(first);
+            if i > 0 {
+                self.right[node - 1] = node;
+                self.left[first] = node;
+            }
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.down[self.up[header]] = node;
+            self.up[header] = node;
+            self.column.push(header);
+            self.row.push(row);
+            self.size[header] += 1;
+        }
+        row
+    }
+
+    fn cover(&mut self, c: usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            self.hide_others(i);
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            self.unhide_others(i);
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+
+    /// Removes the other nodes in the row of `i` from their columns.
+    fn hide_others(&mut self, i: usize) {
+        let mut j = self.right[i];
+        while j != i {
+            self.up[self.down[j]] = self.up[j];
+            self.down[self.up[j]] = self.down[j];
+            self.size[self.column[j]] -= 1;
+            j = self.right[j];
+        }
+    }
+
+    fn unhide_others(&mut self, i: usize) {
+        let mut j = self.left[i];
+        while j != i {
+            self.size[self.column[j]] += 1;
+            self.up[self.down[j]] = j;
+            self.down[self.up[j]] = j;
+            j = self.left[j];
+        }
+    }
+
+    /// Removes the entire row of `r` from its columns.
+    fn hide_row(&mut self, r: usize) {
+        self.hide_others(r);
+        self.up[self.down[r]] = self.up[r];
+        self.down[self.up[r]] = self.down[r];
+        self.size[self.column[r]] -= 1;
+    }
+
+    fn unhide_row(&mut self, r: usize) {
+        self.size[self.column[r]] += 1;
+        self.up[self.down[r]] = r;
+        self.down[self.up[r]] = r;
+        self.unhide_others(r);
+    }
+
+    /// Covers the other columns in the row of `r`,
+    /// once they've been covered as many times as their multiplicity.
+    fn cover_row(&mut self, r: usize) {
+        let mut j = self.right[r];
+        while j != r {
+            let c = self.column[j];
+            self.bound[c] -= 1;
+            if self.bound[c] == 0 {
+                self.cover(c);
+            }
+            j = self.right[j];
+        }
+    }
+
+    fn uncover_row(&mut self, r: usize) {
+        let mut j = self.left[r];
+        while j != r {
+            let c = self.column[j];
+            if self.bound[c] == 0 {
+                self.uncover(c);
+            }
+            self.bound[c] += 1;
+            j = self.left[j];
+        }
+    }
+
+    /// Returns an iterator over all solutions, each given as a list of row indices.
+    pub fn solutions(&mut self) -> Solutions<'_> {
+        Solutions {
+            cover: self,
+            levels: Vec::new(),
+            hidden: Vec::new(),
+            state: State::Enter,
+        }
+    }
+
+    /// Calls `f` for every solution, until it returns `ControlFlow::Break`.
+    pub fn for_each_solution<B>(
+        &mut self,
+        mut f: impl FnMut(&[usize]) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        for solution in self.solutions() {
+            f(&solution)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    pub fn first_solution(&mut self) -> Option<Vec<usize>> {
+        self.solutions().next()
+    }
+
+    pub fn count_solutions(&mut self) -> usize {
+        self.solutions().count()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Entering a new level: pick the next column to cover.
+    Enter,
+    /// Trying the current row of the top level.
+    Try,
+    /// Undoing the current row of the top level, and moving on to the next one.
+    Backtrack,
+    Done,
+}
+
+/// A column that we're branching on, and the row we're currently trying in it.
+#[derive(Debug, Copy, Clone)]
+struct Level {
+    column: usize,
+    row: usize,
+    /// Whether the column must be covered more than once,
+    /// in which case it stays available for deeper levels.
+    multiple: bool,
+    /// Where this level's rows start in the stack of hidden rows.
+    hidden_start: usize,
+}
+
+/// An iterator over the solutions of an [`ExactCover`] problem.
+///
+/// The search state lives in the links of the problem itself,
+/// which are restored when the iterator is dropped.
+pub struct Solutions<'a> {
+    cover: &'a mut ExactCover,
+    levels: Vec<Level>,
+    /// Rows that were already tried for a column with a multiplicity.
+    hidden: Vec<usize>,
+    state: State,
+}
+
+impl Solutions<'_> {
+    /// Picks the column with the fewest choices left,
+    /// or `None` if some column can no longer be covered often enough.
+    fn choose_column(&self) -> Option<usize> {
+        let cover = &self.cover;
+        let mut best = None;
+        let mut best_choices = usize::MAX;
+        let mut c = cover.right[ROOT];
+        while c != ROOT {
+            // A column that must be covered `bound` more times by `size` rows
+            // has `size - bound + 1` choices for its first row.
+            let choices = (cover.size[c] + 1).checked_sub(cover.bound[c])?;
+            if choices < best_choices {
+                best = Some(c);
+                best_choices = choices;
+            }
+            c = cover.right[c];
+        }
+        best
+    }
+
+    fn enter_level(&mut self, c: usize) {
+        let multiple = self.cover.bound[c] > 1;
+        if !multiple {
+            self.cover.cover(c);
+        }
+        self.levels.push(Level {
+            column: c,
+            row: self.cover.down[c],
+            multiple,
+            hidden_start: self.hidden.len(),
+        });
+    }
+
+    fn leave_level(&mut self) {
+        let level = self.levels.pop().unwrap();
+        if level.multiple {
+            while self.hidden.len() > level.hidden_start {
+                let r = self.hidden.pop().unwrap();
+                self.cover.unhide_row(r);
+            }
+        } else {
+            self.cover.uncover(level.column);
+        }
+    }
+
+    fn apply_row(&mut self) {
+        let level = *self.levels.last().unwrap();
+        if level.multiple {
+            // Later choices for this column may only use the rows after this one,
+            // so we don't find the same set of rows in a different order.
+            self.cover.hide_row(level.row);
+            self.hidden.push(level.row);
+        }
+        self.cover.cover_row(level.row);
+        self.cover.bound[level.column] -= 1;
+    }
+
+    fn undo_row(&mut self) {
+        let level = *self.levels.last().unwrap();
+        self.cover.bound[level.column] += 1;
+        self.cover.uncover_row(level.row);
+    }
+}
+
+impl Iterator for Solutions<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.state {
+                State::Enter => {
+                    if self.cover.right[ROOT] == ROOT {
+                        // All primary columns are covered.
+                        self.state = State::Backtrack;
+                        let solution = self
+                            .levels
+                            .iter()
+                            .map(|level| self.cover.row[level.row])
+                            .collect();
+                        return Some(solution);
+                    }
+                    if let Some(c) = self.choose_column() {
+                        self.enter_level(c);
+                        self.state = State::Try;
+                    } else {
+                        self.state = State::Backtrack;
+                    }
+                }
+                State::Try => {
+                    let level = self.levels.last().unwrap();
+                    if level.row == level.column {
+                        // Tried every row in this column.
+                        self.leave_level();
+                        self.state = State::Backtrack;
+                        continue;
+                    }
+                    self.apply_row();
+                    self.state = State::Enter;
+                }
+                State::Backtrack => {
+                    if self.levels.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+                    self.undo_row();
+                    let level = self.levels.last_mut().unwrap();
+                    level.row = self.cover.down[level.row];
+                    self.state = State::Try;
+                }
+                State::Done => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Solutions<'_> {
+    fn drop(&mut self) {
+        // Unwind the search, so the problem can be solved again.
+        // Every level has its current row applied, except the top one while we're trying it.
+        if self.state == State::Try {
+            self.leave_level();
+        }
+        while !self.levels.is_empty() {
+            self.undo_row();
+            self.leave_level();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from Knuth's "Dancing Links" paper.
+    fn knuth_example() -> ExactCover {
+        let mut cover = ExactCover::new(7, 0);
+        cover.add_row(&[2, 4, 5]);
+        cover.add_row(&[0, 3, 6]);
+        cover.add_row(&[1, 2, 5]);
+        cover.add_row(&[0, 3]);
+        cover.add_row(&[1, 6]);
+        cover.add_row(&[3, 4, 6]);
+        cover
+    }
+
+    #[test]
+    fn test_first_solution() {
+        let mut cover = knuth_example();
+        let mut solution = cover.first_solution().unwrap();
+        solution.sort();
+        assert_eq!(solution, vec![0, 3, 4]);
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let mut cover = ExactCover::new(2, 0);
+        cover.add_row(&[0]);
+        cover.add_row(&[1]);
+        cover.add_row(&[0, 1]);
+        cover.add_row(&[0]);
+        assert_eq!(cover.count_solutions(), 3);
+        // Solving again gives the same result.
+        assert_eq!(cover.count_solutions(), 3);
+    }
+
+    #[test]
+    fn test_secondary_columns() {
+        // Rows 0 and 1 both use secondary column 2, so they can't be combined.
+        let mut cover = ExactCover::new(2, 1);
+        cover.add_row(&[0, 2]);
+        cover.add_row(&[1, 2]);
+        cover.add_row(&[1]);
+        assert_eq!(cover.solutions().collect::<Vec<_>>(), vec![vec![0, 2]]);
+    }
+
+    #[test]
+    fn test_multiplicity() {
+        // Place dominoes in a row of four cells.
+        let mut cover = ExactCover::new(1, 4);
+        cover.set_multiplicity(0, 2);
+        cover.add_row(&[0, 1, 2]);
+        cover.add_row(&[0, 2, 3]);
+        cover.add_row(&[0, 3, 4]);
+        assert_eq!(cover.solutions().collect::<Vec<_>>(), vec![vec![0, 2]]);
+        cover.set_multiplicity(0, 3);
+        assert_eq!(cover.count_solutions(), 0);
+    }
+
+    #[test]
+    fn test_stop_early() {
+        let mut cover = knuth_example();
+        cover.add_row(&[0, 1, 2, 3, 4, 5, 6]);
+        let mut found = 0;
+        let result = cover.for_each_solution(|_| {
+            found += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(result, ControlFlow::Break(()));
+        assert_eq!(found, 1);
+        assert_eq!(cover.count_solutions(), 2);
+    }
+}
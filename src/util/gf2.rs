@@ -0,0 +1,158 @@
+use bitvec::prelude::*;
+
+/// A system of linear equations `A * x = b` over GF(2), kept in reduced row echelon form.
+#[derive(Debug, Clone)]
+pub struct Gf2System {
+    num_vars: usize,
+    rows: Vec<BitVec>,
+    rhs: BitVec,
+    pivots: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl Gf2System {
+    /// Creates a system from its equations, where each row holds the coefficients of one equation.
+    pub fn new(num_vars: usize, rows: Vec<BitVec>, rhs: BitVec) -> Self {
+        assert_eq!(rows.len(), rhs.len());
+        assert!(rows.iter().all(|row| row.len() == num_vars));
+        let mut system = Self {
+            num_vars,
+            rows,
+            rhs,
+            pivots: Vec::new(),
+        };
+        system.row_reduce();
+        system
+    }
+
+    /// Creates a system from its columns, where each column holds the coefficients of one variable.
+    pub fn from_columns(columns: &[BitVec], rhs: BitVec) -> Self {
+        assert!(columns.iter().all(|column| column.len() == rhs.len()));
+        let rows = (0..rhs.len())
+            .map(|i| columns.iter().map(|column| column[i]).collect())
+            .collect();
+        Self::new(columns.len(), rows, rhs)
+    }
+
+    fn row_reduce(&mut self) {
+        let mut rank = 0;
+        for column in 0..self.num_vars {
+            let Some(pivot) = (rank..self.rows.len()).find(|&r| self.rows[r][column]) else {
+                continue;
+            };
+            self.rows.swap(rank, pivot);
+            self.rhs.swap(rank, pivot);
+            let (pivot_row, pivot_rhs) = (self.rows[rank].clone(), self.rhs[rank]);
+            for r in 0..self.rows.len() {
+                if r != rank && self.rows[r][column] {
+                    self.rows[r] ^= &pivot_row;
+                    let rhs = self.rhs[r] ^ pivot_rhs;
+                    self.rhs.set(r, rhs);
+                }
+            }
+            self.pivots.push(column);
+            rank += 1;
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.num_vars
+    }
+
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+
+    /// Returns whether the system has at least one solution.
+    pub fn is_consistent(&self) -> bool {
+        // Rows below the rank are all zero, so their right-hand side must be zero too.
+        self.rhs[self.rank()..].not_any()
+    }
+
+    fn free_variables(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.num_vars).filter(|column| !self.pivots.contains(column))
+    }
+
+    /// Returns a basis for the solutions of `A * x = 0`.
+    pub fn null_space(&self) -> Vec<BitVec> {
+        self.free_variables()
+            .map(|free| {
+                let mut vector = bitvec!(0; self.num_vars);
+                vector.set(free, true);
+                for (row, &pivot) in self.rows.iter().zip(&self.pivots) {
+                    vector.set(pivot, row[free]);
+                }
+                vector
+            })
+            .collect()
+    }
+
+    /// Returns the solution with all free variables set to zero, if there is a solution.
+    pub fn particular_solution(&self) -> Option<BitVec> {
+        if !self.is_consistent() {
+            return None;
+        }
+        let mut solution = bitvec!(0; self.num_vars);
+        for (r, &pivot) in self.pivots.iter().enumerate() {
+            solution.set(pivot, self.rhs[r]);
+        }
+        Some(solution)
+    }
+
+    /// Finds the solution with the fewest ones by enumerating the null space.
+    ///
+    /// Ties are broken by choosing the lexicographically smallest solution.
+    pub fn min_weight_solution(&self) -> Option<BitVec> {
+        let mut solution = self.particular_solution()?;
+        let basis = self.null_space();
+        assert!(basis.len() < 64, "null space too large to enumerate");
+        let mut best = solution.clone();
+        // Walk through all combinations of basis vectors in Gray code order,
+        // so every step only needs to flip in a single basis vector.
+        for i in 1u64..(1 << basis.len()) {
+            solution ^= &basis[i.trailing_zeros() as usize];
+            if (solution.count_ones(), &solution) < (best.count_ones(), &best) {
+                best = solution.clone();
+            }
+        }
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank() {
+        let system = Gf2System::new(
+            3,
+            vec![bitvec![1, 1, 0], bitvec![0, 1, 1], bitvec![1, 0, 1]],
+            bitvec![0, 0, 0],
+        );
+        assert_eq!(system.rank(), 2);
+        assert_eq!(system.null_space(), vec![bitvec![1, 1, 1]]);
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        let system = Gf2System::new(2, vec![bitvec![1, 1], bitvec![1, 1]], bitvec![0, 1]);
+        assert!(!system.is_consistent());
+        assert_eq!(system.min_weight_solution(), None);
+    }
+
+    #[test]
+    fn test_min_weight_solution() {
+        // x0 + x1 = 1, x1 + x2 = 0, x2 + x3 = 0
+        let system = Gf2System::new(
+            4,
+            vec![
+                bitvec![1, 1, 0, 0],
+                bitvec![0, 1, 1, 0],
+                bitvec![0, 0, 1, 1],
+            ],
+            bitvec![1, 0, 0],
+        );
+        assert_eq!(system.min_weight_solution(), Some(bitvec![1, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,381 @@
+use bimap::BiMap;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::{Display, Formatter};
+
+/// A node of a [`Graph`], numbered in the order in which its label first appeared.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct NodeId(pub u32);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Adjacency lists in compressed sparse row format:
+/// the neighbours of node `i` are `targets[offsets[i]..offsets[i + 1]]`.
+#[derive(Debug, Clone)]
+struct Adjacency {
+    offsets: Vec<usize>,
+    targets: Vec<NodeId>,
+}
+
+impl Adjacency {
+    fn new(len: usize, edges: &[(NodeId, NodeId)]) -> Self {
+        let mut offsets = vec![0; len + 1];
+        for &(from, _) in edges {
+            offsets[from.index() + 1] += 1;
+        }
+        for i in 0..len {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut next = offsets.clone();
+        let mut targets = vec![NodeId(0); edges.len()];
+        for &(from, to) in edges {
+            targets[next[from.index()]] = to;
+            next[from.index()] += 1;
+        }
+        Self { offsets, targets }
+    }
+
+    fn neighbours(&self, node: NodeId) -> &[NodeId] {
+        &self.targets[self.offsets[node.index()]..self.offsets[node.index() + 1]]
+    }
+}
+
+/// A directed graph with labelled nodes.
+#[derive(Debug, Clone)]
+pub struct Graph {
+    labels: BiMap<String, NodeId>,
+    /// Whether each node has an adjacency list of its own.
+    declared: Vec<bool>,
+    successors: Adjacency,
+    predecessors: Adjacency,
+}
+
+/// A problem with the structure of a [`Graph`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GraphError {
+    /// The labels along a cycle, each one leading to the next and the last one back to the first.
+    Cycle(Vec<String>),
+    /// An edge to a node without an adjacency list of its own.
+    Dangling { from: String, to: String },
+    /// A label that doesn't belong to any node.
+    UnknownLabel(String),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Cycle(labels) => {
+                write!(f, "cycle: {} -> {}", labels.join(" -> "), labels[0])
+            }
+            GraphError::Dangling { from, to } => {
+                write!(f, "{from} leads to {to}, which has no outputs listed")
+            }
+            GraphError::UnknownLabel(label) => write!(f, "unknown label {label}"),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+#[allow(dead_code)]
+impl Graph {
+    /// Parses adjacency lists like `aaa: bbb ccc`, with one line per node.
+    /// Nodes that only appear as a target don't need a line of their own.
+    pub fn parse(input: &str) -> Self {
+        let mut labels = BiMap::new();
+        let mut intern = |label: &str| -> NodeId {
+            if let Some(&id) = labels.get_by_left(label) {
+                return id;
+            }
+            let id = NodeId(labels.len() as u32);
+            labels.insert(label.to_string(), id);
+            id
+        };
+        let mut edges = Vec::new();
+        let mut declared = Vec::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let (from, targets) = line.split_once(':').expect("expected a colon");
+            let from = intern(from.trim());
+            declared.push(from);
+            for to in targets.split_whitespace() {
+                edges.push((from, intern(to)));
+            }
+        }
+        Self::from_edges(labels, &declared, &edges)
+    }
+
+    fn from_edges(
+        labels: BiMap<String, NodeId>,
+        declared_nodes: &[NodeId],
+        edges: &[(NodeId, NodeId)],
+    ) -> Self {
+        let mut declared = vec![false; labels.len()];
+        for node in declared_nodes {
+            declared[node.index()] = true;
+        }
+        let reversed = edges
+            .iter()
+            .map(|&(from, to)| (to, from))
+            .collect::<Vec<_>>();
+        Self {
+            declared,
+            successors: Adjacency::new(labels.len(), edges),
+            predecessors: Adjacency::new(labels.len(), &reversed),
+            labels,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.successors.targets.len()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + use<> {
+        (0..self.len() as u32).map(NodeId)
+    }
+
+    pub fn node(&self, label: &str) -> Option<NodeId> {
+        self.labels.get_by_left(label).copied()
+    }
+
+    pub fn label(&self, node: NodeId) -> &str {
+        self.labels.get_by_right(&node).expect("unknown node")
+    }
+
+    fn labels_of(&self, nodes: &[NodeId]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|&node| self.label(node).to_string())
+            .collect()
+    }
+
+    /// Whether the node has an adjacency list of its own,
+    /// rather than only appearing as the target of other nodes.
+    pub fn is_declared(&self, node: NodeId) -> bool {
+        self.declared[node.index()]
+    }
+
+    pub fn successors(&self, node: NodeId) -> &[NodeId] {
+        self.successors.neighbours(node)
+    }
+
+    pub fn predecessors(&self, node: NodeId) -> &[NodeId] {
+        self.predecessors.neighbours(node)
+    }
+
+    /// Orders the nodes so that every edge goes forward, or returns `None` if there is a cycle.
+    ///
+    /// Among the valid orders, this picks the one that prefers nodes with a smaller id.
+    pub fn topological_sort(&self) -> Option<Vec<NodeId>> {
+        let mut in_degree = self
+            .nodes()
+            .map(|node| self.predecessors(node).len())
+            .collect::<Vec<_>>();
+        let mut ready = self
+            .nodes()
+            .filter(|node| in_degree[node.index()] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(Reverse(node)) = ready.pop() {
+            order.push(node);
+            for &next in self.successors(node) {
+                in_degree[next.index()] -= 1;
+                if in_degree[next.index()] == 0 {
+                    ready.push(Reverse(next));
+                }
+            }
+        }
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// Orders the nodes reachable from `roots` so that every edge between them goes forward.
+    /// If any of them lie on a cycle, returns that cycle instead.
+    pub fn topological_sort_from(
+        &self,
+        roots: impl IntoIterator<Item = NodeId>,
+    ) -> Result<Vec<NodeId>, GraphError> {
+        self.depth_first_order(roots)
+            .map_err(|cycle| GraphError::Cycle(self.labels_of(&cycle)))
+    }
+
+    /// Finds a cycle, as the list of nodes along it starting from any of them.
+    pub fn find_cycle(&self) -> Option<Vec<NodeId>> {
+        self.depth_first_order(self.nodes()).err()
+    }
+
+    /// Returns the nodes reachable from `roots` in reverse post-order,
+    /// or the first cycle that a depth-first search runs into.
+    fn depth_first_order(
+        &self,
+        roots: impl IntoIterator<Item = NodeId>,
+    ) -> Result<Vec<NodeId>, Vec<NodeId>> {
+        #[derive(Copy, Clone, Eq, PartialEq)]
+        enum State {
+            Unvisited,
+            OnPath,
+            Done,
+        }
+        let mut state = vec![State::Unvisited; self.len()];
+        let mut finished = Vec::new();
+        for root in roots {
+            if state[root.index()] != State::Unvisited {
+                continue;
+            }
+            // The current path, with the number of successors already explored for each node.
+            let mut path = vec![(root, 0)];
+            state[root.index()] = State::OnPath;
+            while let Some((node, explored)) = path.last_mut() {
+                let node = *node;
+                let Some(&next) = self.successors(node).get(*explored) else {
+                    state[node.index()] = State::Done;
+                    finished.push(node);
+                    path.pop();
+                    continue;
+                };
+                *explored += 1;
+                match state[next.index()] {
+                    State::Unvisited => {
+                        state[next.index()] = State::OnPath;
+                        path.push((next, 0));
+                    }
+                    State::OnPath => {
+                        let start = path.iter().position(|&(other, _)| other == next).unwrap();
+                        return Err(path[start..].iter().map(|&(node, _)| node).collect());
+                    }
+                    State::Done => {}
+                }
+            }
+        }
+        finished.reverse();
+        Ok(finished)
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    /// Splits the graph into strongly connected components, using Kosaraju's algorithm.
+    ///
+    /// The components are listed in topological order, so edges between components only go forward.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        // First, order the nodes by when a depth-first search finishes them.
+        let mut visited = vec![false; self.len()];
+        let mut finished = Vec::with_capacity(self.len());
+        for root in self.nodes() {
+            if visited[root.index()] {
+                continue;
+            }
+            visited[root.index()] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((node, explored)) = stack.last_mut() {
+                let node = *node;
+                match self.successors(node).get(*explored) {
+                    Some(&next) => {
+                        *explored += 1;
+                        if !visited[next.index()] {
+                            visited[next.index()] = true;
+                            stack.push((next, 0));
+                        }
+                    }
+                    None => {
+                        finished.push(node);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        // Then, the nodes that can reach the last finished node form a source component.
+        let mut component_of = vec![None; self.len()];
+        let mut components = Vec::new();
+        for &root in finished.iter().rev() {
+            if component_of[root.index()].is_some() {
+                continue;
+            }
+            let mut component = vec![root];
+            component_of[root.index()] = Some(components.len());
+            let mut i = 0;
+            while let Some(&node) = component.get(i) {
+                for &previous in self.predecessors(node) {
+                    if component_of[previous.index()].is_none() {
+                        component_of[previous.index()] = Some(components.len());
+                        component.push(previous);
+                    }
+                }
+                i += 1;
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let graph = Graph::parse("a: b c\nb: c\nd: a\n");
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.num_edges(), 4);
+        let a = graph.node("a").unwrap();
+        let c = graph.node("c").unwrap();
+        assert_eq!(graph.labels_of(graph.successors(a)), ["b", "c"]);
+        assert_eq!(graph.labels_of(graph.predecessors(c)), ["a", "b"]);
+        assert_eq!(graph.labels_of(graph.predecessors(a)), ["d"]);
+        assert!(graph.successors(c).is_empty());
+        assert_eq!(graph.node("e"), None);
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let graph = Graph::parse("a: b c\nb: c\nd: a\n");
+        let order = graph.topological_sort().unwrap();
+        assert_eq!(graph.labels_of(&order), ["d", "a", "b", "c"]);
+        assert!(!graph.has_cycle());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let graph = Graph::parse("a: b\nb: c\nc: d b\nd: e\n");
+        assert_eq!(graph.topological_sort(), None);
+        let cycle = graph.find_cycle().unwrap();
+        assert_eq!(graph.labels_of(&cycle), ["b", "c"]);
+        let components = graph
+            .strongly_connected_components()
+            .iter()
+            .map(|component| graph.labels_of(component))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            components,
+            [vec!["a"], vec!["b", "c"], vec!["d"], vec!["e"]]
+        );
+    }
+
+    #[test]
+    fn test_topological_sort_from() {
+        let graph = Graph::parse("a: b\nb: c\nc: b\nd: e\ne: f\n");
+        let d = graph.node("d").unwrap();
+        let order = graph.topological_sort_from([d]).unwrap();
+        assert_eq!(graph.labels_of(&order), ["d", "e", "f"]);
+        let a = graph.node("a").unwrap();
+        let error = graph.topological_sort_from([a]).unwrap_err();
+        assert_eq!(error, GraphError::Cycle(vec!["b".into(), "c".into()]));
+        assert_eq!(error.to_string(), "cycle: b -> c -> b");
+        assert!(graph.is_declared(d));
+        assert!(!graph.is_declared(graph.node("f").unwrap()));
+    }
+}
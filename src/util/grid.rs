@@ -0,0 +1,184 @@
+use super::Vector2D;
+use std::ops::{Index, IndexMut};
+
+/// A dense rectangular grid, stored row by row.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Self {
+        assert_eq!(cells.len(), width * height, "wrong number of cells");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(width, height, vec![value; width * height])
+    }
+
+    /// Parses a map with one character per cell.
+    ///
+    /// Panics if the lines don't all have the same length.
+    pub fn parse(input: &str, mut f: impl FnMut(char) -> T) -> Self {
+        let mut width = None;
+        let mut cells = Vec::new();
+        let mut height = 0;
+        for line in input.lines() {
+            let len_before = cells.len();
+            cells.extend(line.chars().map(&mut f));
+            let line_width = cells.len() - len_before;
+            assert_eq!(
+                *width.get_or_insert(line_width),
+                line_width,
+                "line {} has a different width",
+                height + 1
+            );
+            height += 1;
+        }
+        Self::new(width.unwrap_or(0), height, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, pos: Vector2D) -> bool {
+        (0..self.width as i32).contains(&pos.x()) && (0..self.height as i32).contains(&pos.y())
+    }
+
+    fn index_of(&self, pos: Vector2D) -> Option<usize> {
+        self.in_bounds(pos)
+            .then(|| (pos.y() as usize) * self.width + (pos.x() as usize))
+    }
+
+    pub fn get(&self, pos: Vector2D) -> Option<&T> {
+        self.index_of(pos).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, pos: Vector2D) -> Option<&mut T> {
+        self.index_of(pos).map(|i| &mut self.cells[i])
+    }
+
+    /// All positions in reading order.
+    pub fn positions(&self) -> impl Iterator<Item = Vector2D> + use<T> {
+        let width = self.width as i32;
+        (0..self.height as i32).flat_map(move |y| (0..width).map(move |x| Vector2D::new(x, y)))
+    }
+
+    /// All positions and their cells in reading order.
+    pub fn cells(&self) -> impl Iterator<Item = (Vector2D, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// Positions of all cells matching a predicate, in reading order.
+    pub fn positions_where(&self, mut f: impl FnMut(&T) -> bool) -> impl Iterator<Item = Vector2D> {
+        self.cells()
+            .filter(move |&(_, cell)| f(cell))
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // Empty grids would make `chunks_exact` panic.
+        self.cells.chunks_exact(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |x| self.cells[x..].iter().step_by(self.width))
+    }
+
+    /// The horizontally and vertically adjacent positions that lie inside the grid.
+    pub fn neighbours(&self, pos: Vector2D) -> impl Iterator<Item = Vector2D> + '_ {
+        pos.neighbours().filter(|&pos| self.in_bounds(pos))
+    }
+
+    /// All adjacent positions, including diagonals, that lie inside the grid.
+    pub fn neighbours_diagonal(&self, pos: Vector2D) -> impl Iterator<Item = Vector2D> + '_ {
+        pos.neighbours_diagonal().filter(|&pos| self.in_bounds(pos))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.width, self.height, self.cells.iter().map(f).collect())
+    }
+}
+
+impl<T> Index<Vector2D> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Vector2D) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("position {pos} out of bounds"))
+    }
+}
+
+impl<T> IndexMut<Vector2D> for Grid<T> {
+    fn index_mut(&mut self, pos: Vector2D) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("position {pos} out of bounds"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::parse("abc\ndef\n", |c| c)
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[Vector2D::new(2, 1)], 'f');
+        assert_eq!(grid.get(Vector2D::new(3, 0)), None);
+        assert_eq!(grid.get(Vector2D::new(0, -1)), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_parse_ragged() {
+        Grid::parse("abc\nde\n", |c| c);
+    }
+
+    #[test]
+    fn test_iteration() {
+        let grid = example();
+        let rows = grid
+            .rows()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, ["abc", "def"]);
+        let columns = grid
+            .columns()
+            .map(|column| column.collect::<String>())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, ["ad", "be", "cf"]);
+        assert_eq!(
+            grid.positions_where(|&c| c == 'e').collect::<Vec<_>>(),
+            [Vector2D::new(1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+        assert_eq!(grid.neighbours(Vector2D::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbours_diagonal(Vector2D::new(0, 0)).count(), 3);
+        assert_eq!(grid.neighbours_diagonal(Vector2D::new(1, 0)).count(), 5);
+    }
+}
@@ -0,0 +1,239 @@
+use super::gcd;
+
+/// An integer linear program of the form `A * x = b`, with `0 <= x[j] <= upper_bounds[j]`.
+#[derive(Debug, Clone)]
+pub struct IntegerProgram {
+    coefficients: Vec<Vec<i64>>,
+    targets: Vec<i64>,
+    upper_bounds: Vec<i64>,
+}
+
+/// A pivot row of the reduced system: `divisor * x[column] + sum(free_coefficients[f] * x[free[f]]) = target`.
+#[derive(Debug, Clone)]
+struct PivotRow {
+    column: usize,
+    divisor: i64,
+    free_coefficients: Vec<i64>,
+    target: i64,
+}
+
+/// The system in reduced row echelon form, expressed in terms of its free variables.
+#[derive(Debug, Clone)]
+struct ReducedSystem {
+    pivots: Vec<PivotRow>,
+    free: Vec<usize>,
+}
+
+#[allow(dead_code)]
+impl IntegerProgram {
+    pub fn new(coefficients: Vec<Vec<i64>>, targets: Vec<i64>, upper_bounds: Vec<i64>) -> Self {
+        assert_eq!(coefficients.len(), targets.len());
+        assert!(
+            coefficients
+                .iter()
+                .all(|row| row.len() == upper_bounds.len())
+        );
+        assert!(upper_bounds.iter().all(|&bound| bound >= 0));
+        Self {
+            coefficients,
+            targets,
+            upper_bounds,
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.upper_bounds.len()
+    }
+
+    /// Finds a solution that minimizes `costs · x`, or `None` if there is no solution.
+    pub fn minimize(&self, costs: &[i64]) -> Option<Vec<i64>> {
+        assert_eq!(costs.len(), self.num_variables());
+        let reduced = self.reduce()?;
+        let mut search = Search {
+            program: self,
+            reduced: &reduced,
+            costs,
+            prune: costs.iter().all(|&cost| cost >= 0),
+            free_values: vec![0; reduced.free.len()],
+            best: None,
+        };
+        search.run(0, 0);
+        search.best.map(|(_, solution)| solution)
+    }
+
+    /// Finds a solution with the fewest total units, or `None` if there is no solution.
+    pub fn minimize_sum(&self) -> Option<Vec<i64>> {
+        self.minimize(&vec![1; self.num_variables()])
+    }
+
+    /// Gaussian elimination over the rationals, keeping every row integral
+    /// by cross-multiplying and dividing out the row's GCD.
+    fn reduce(&self) -> Option<ReducedSystem> {
+        let num_vars = self.num_variables();
+        let mut rows = self
+            .coefficients
+            .iter()
+            .zip(&self.targets)
+            .map(|(row, &target)| {
+                let mut row = row.clone();
+                row.push(target);
+                row
+            })
+            .collect::<Vec<_>>();
+        let mut pivot_columns = Vec::new();
+        let mut rank = 0;
+        for column in 0..num_vars {
+            let Some(pivot) = (rank..rows.len()).find(|&r| rows[r][column] != 0) else {
+                continue;
+            };
+            rows.swap(rank, pivot);
+            for r in 0..rows.len() {
+                if r == rank || rows[r][column] == 0 {
+                    continue;
+                }
+                let pivot_row = rows[rank].clone();
+                let (p, q) = (pivot_row[column], rows[r][column]);
+                for (x, &y) in rows[r].iter_mut().zip(&pivot_row) {
+                    *x = *x * p - y * q;
+                }
+                normalize_row(&mut rows[r]);
+            }
+            pivot_columns.push(column);
+            rank += 1;
+        }
+        // Remaining rows are all-zero on the left-hand side, so they must be zero on the right.
+        if rows[rank..].iter().any(|row| row[num_vars] != 0) {
+            return None;
+        }
+        let free = (0..num_vars)
+            .filter(|column| !pivot_columns.contains(column))
+            .collect::<Vec<_>>();
+        let pivots = pivot_columns
+            .into_iter()
+            .zip(rows)
+            .map(|(column, mut row)| {
+                if row[column] < 0 {
+                    row.iter_mut().for_each(|x| *x = -*x);
+                }
+                PivotRow {
+                    column,
+                    divisor: row[column],
+                    free_coefficients: free.iter().map(|&f| row[f]).collect(),
+                    target: row[num_vars],
+                }
+            })
+            .collect();
+        Some(ReducedSystem { pivots, free })
+    }
+}
+
+fn normalize_row(row: &mut [i64]) {
+    let divisor = row.iter().copied().fold(0, gcd);
+    if divisor > 1 {
+        row.iter_mut().for_each(|x| *x /= divisor);
+    }
+}
+
+/// Bounded depth-first search over the values of the free variables.
+struct Search<'a> {
+    program: &'a IntegerProgram,
+    reduced: &'a ReducedSystem,
+    costs: &'a [i64],
+    prune: bool,
+    free_values: Vec<i64>,
+    best: Option<(i64, Vec<i64>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self, index: usize, partial_cost: i64) {
+        if self.prune
+            && let Some((best_cost, _)) = self.best
+            && partial_cost >= best_cost
+        {
+            // With non-negative costs, the pivot variables can only add to the cost.
+            return;
+        }
+        if index == self.reduced.free.len() {
+            self.evaluate();
+            return;
+        }
+        let variable = self.reduced.free[index];
+        for value in 0..=self.program.upper_bounds[variable] {
+            self.free_values[index] = value;
+            self.run(index + 1, partial_cost + self.costs[variable] * value);
+        }
+    }
+
+    fn evaluate(&mut self) {
+        let mut solution = vec![0; self.program.num_variables()];
+        for (&variable, &value) in self.reduced.free.iter().zip(&self.free_values) {
+            solution[variable] = value;
+        }
+        for pivot in &self.reduced.pivots {
+            let remainder = pivot.target
+                - pivot
+                    .free_coefficients
+                    .iter()
+                    .zip(&self.free_values)
+                    .map(|(&coefficient, &value)| coefficient * value)
+                    .sum::<i64>();
+            if remainder % pivot.divisor != 0 {
+                return;
+            }
+            let value = remainder / pivot.divisor;
+            if value < 0 || value > self.program.upper_bounds[pivot.column] {
+                return;
+            }
+            solution[pivot.column] = value;
+        }
+        let cost = solution
+            .iter()
+            .zip(self.costs)
+            .map(|(&value, &cost)| value * cost)
+            .sum::<i64>();
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|(best_cost, _)| cost < *best_cost)
+        {
+            self.best = Some((cost, solution));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unique_solution() {
+        // x + y = 3, x - y = 1
+        let program = IntegerProgram::new(vec![vec![1, 1], vec![1, -1]], vec![3, 1], vec![10, 10]);
+        assert_eq!(program.minimize_sum(), Some(vec![2, 1]));
+    }
+
+    #[test]
+    fn test_free_variable() {
+        // x + y = 4, y + z = 4: minimal when y = 4
+        let program = IntegerProgram::new(
+            vec![vec![1, 1, 0], vec![0, 1, 1]],
+            vec![4, 4],
+            vec![4, 4, 4],
+        );
+        assert_eq!(program.minimize_sum(), Some(vec![0, 4, 0]));
+    }
+
+    #[test]
+    fn test_non_integral() {
+        // 2x = 3
+        let program = IntegerProgram::new(vec![vec![2]], vec![3], vec![10]);
+        assert_eq!(program.minimize_sum(), None);
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        // x + y = 1, x + y = 2
+        let program = IntegerProgram::new(vec![vec![1, 1], vec![1, 1]], vec![1, 2], vec![5, 5]);
+        assert_eq!(program.minimize_sum(), None);
+    }
+}
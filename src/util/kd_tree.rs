@@ -0,0 +1,560 @@
+use super::{Num, Vector};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// How to measure the distance between two points.
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Metric {
+    /// Straight-line distance. Distances are reported squared, so they stay exact for integers.
+    Euclidean,
+    /// Sum of the absolute differences along each axis.
+    Manhattan,
+}
+
+impl Metric {
+    pub fn distance<const N: usize, T: Num>(self, a: &Vector<N, T>, b: &Vector<N, T>) -> T {
+        match self {
+            Metric::Euclidean => a.euclidean_distance_squared(b),
+            Metric::Manhattan => (*a - *b).manhattan_distance(),
+        }
+    }
+
+    /// The smallest possible distance between two points that are `difference` apart along one axis.
+    fn axis_distance<T: Num>(self, difference: T) -> T {
+        match self {
+            Metric::Euclidean => difference * difference,
+            Metric::Manhattan => difference.abs(),
+        }
+    }
+}
+
+/// A k-d tree for nearest neighbour queries on a fixed set of points.
+///
+/// Points are referred to by their index in the slice that the tree was built from.
+/// Distances are measured with a [`Metric`], and ties are broken by the smallest index.
+#[derive(Debug, Clone)]
+pub struct KdTree<const N: usize, T: Num = i32> {
+    points: Vec<Vector<N, T>>,
+    /// Point indices, laid out as an implicit balanced tree:
+    /// the median of every range is its root, splitting along axis `depth % N`.
+    order: Vec<usize>,
+}
+
+/// Below this many points, scanning them all is faster than searching the tree.
+const LEAF_SIZE: usize = 8;
+
+fn compare<T: Num>(a: T, b: T) -> Ordering {
+    a.partial_cmp(&b).expect("coordinates must be comparable")
+}
+
+#[allow(dead_code)]
+impl<const N: usize, T: Num> KdTree<N, T> {
+    pub fn new(points: &[Vector<N, T>]) -> Self {
+        let mut order = (0..points.len()).collect::<Vec<_>>();
+        build(points, &mut order, 0);
+        Self {
+            points: points.to_vec(),
+            order,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn points(&self) -> &[Vector<N, T>] {
+        &self.points
+    }
+
+    /// The `k` points closest to `query`, closest first, with their distances.
+    pub fn nearest(&self, query: &Vector<N, T>, k: usize, metric: Metric) -> Vec<(usize, T)> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.search_nearest(query, k, metric, 0, 0..self.order.len(), 0, &mut best);
+        }
+        best
+    }
+
+    /// Searches for the `k` points closest to `query` among those with an index of at least `min_index`.
+    #[allow(clippy::too_many_arguments)]
+    fn search_nearest(
+        &self,
+        query: &Vector<N, T>,
+        k: usize,
+        metric: Metric,
+        min_index: usize,
+        range: std::ops::Range<usize>,
+        depth: usize,
+        best: &mut Vec<(usize, T)>,
+    ) {
+        if range.len() <= LEAF_SIZE {
+            for &index in &self.order[range] {
+                if index >= min_index {
+                    insert_nearest(best, k, index, metric.distance(query, &self.points[index]));
+                }
+            }
+            return;
+        }
+        let mid = range.start + range.len() / 2;
+        let index = self.order[mid];
+        let point = &self.points[index];
+        if index >= min_index {
+            insert_nearest(best, k, index, metric.distance(query, point));
+        }
+        // Search the side containing the query first, then the other side if it could still help.
+        let axis = depth % N;
+        let difference = query.coords[axis] - point.coords[axis];
+        let (near, far) = if difference < T::zero() {
+            (range.start..mid, mid + 1..range.end)
+        } else {
+            (mid + 1..range.end, range.start..mid)
+        };
+        self.search_nearest(query, k, metric, min_index, near, depth + 1, best);
+        // Points at exactly the worst distance may still win a tie, so only prune strictly.
+        if best.len() < k || metric.axis_distance(difference) <= best[k - 1].1 {
+            self.search_nearest(query, k, metric, min_index, far, depth + 1, best);
+        }
+    }
+
+    /// All points at most `radius` away from `query`, closest first, with their distances.
+    ///
+    /// For [`Metric::Euclidean`], both the radius and the distances are squared.
+    pub fn within_radius(
+        &self,
+        query: &Vector<N, T>,
+        radius: T,
+        metric: Metric,
+    ) -> Vec<(usize, T)> {
+        let mut found = Vec::new();
+        self.for_each_within_radius(query, radius, metric, |index, distance| {
+            found.push((index, distance));
+        });
+        found.sort_by(|&(i, a), &(j, b)| compare(a, b).then(i.cmp(&j)));
+        found
+    }
+
+    fn for_each_within_radius(
+        &self,
+        query: &Vector<N, T>,
+        radius: T,
+        metric: Metric,
+        mut f: impl FnMut(usize, T),
+    ) {
+        let mut stack = vec![(0..self.order.len(), 0)];
+        while let Some((range, depth)) = stack.pop() {
+            if range.len() <= LEAF_SIZE {
+                for &index in &self.order[range] {
+                    let distance = metric.distance(query, &self.points[index]);
+                    if distance <= radius {
+                        f(index, distance);
+                    }
+                }
+                continue;
+            }
+            let mid = range.start + range.len() / 2;
+            let index = self.order[mid];
+            let point = &self.points[index];
+            let distance = metric.distance(query, point);
+            if distance <= radius {
+                f(index, distance);
+            }
+            let axis = depth % N;
+            let difference = query.coords[axis] - point.coords[axis];
+            let (near, far) = if difference < T::zero() {
+                (range.start..mid, mid + 1..range.end)
+            } else {
+                (mid + 1..range.end, range.start..mid)
+            };
+            stack.push((near, depth + 1));
+            if metric.axis_distance(difference) <= radius {
+                stack.push((far, depth + 1));
+            }
+        }
+    }
+
+    /// All pairs of distinct points `(i, j, distance)` with `i < j`,
+    /// in increasing order of distance, then of `i`, then of `j`.
+    ///
+    /// Pairs are found lazily from the nearest neighbours of each point,
+    /// so taking only the closest few is cheap.
+    pub fn pairs(&self, metric: Metric) -> Pairs<'_, N, T> {
+        let mut pairs = Pairs {
+            tree: self,
+            metric,
+            neighbours: vec![Vec::new(); self.len()],
+            next: vec![0; self.len()],
+            queue: BinaryHeap::with_capacity(self.len()),
+        };
+        for i in 0..self.len() {
+            pairs.advance(i);
+        }
+        pairs
+    }
+
+    /// A minimum spanning tree of the points, as edges `(i, j, distance)` with `i < j`,
+    /// in the order that [`pairs`](Self::pairs) would return them.
+    ///
+    /// Uses Borůvka's algorithm: every round connects each component to the closest point
+    /// outside of it, so only nearest neighbour searches are needed, even with outliers.
+    pub fn minimum_spanning_tree(&self, metric: Metric) -> Vec<(usize, usize, T)> {
+        let mut component = (0..self.len()).collect::<Vec<_>>();
+        let mut num_components = self.len();
+        let mut edges = Vec::with_capacity(self.len().saturating_sub(1));
+        // The closest pair between each point and another component, if known.
+        // It stays valid until the other point joins the same component.
+        let mut nearest_outside = vec![None; self.len()];
+        while num_components > 1 {
+            let uniform = self.uniform_components(&component);
+            let mut closest = vec![None; self.len()];
+            for i in 0..self.len() {
+                let best = &mut closest[component[i]];
+                if let Some(pair @ (_, first, second)) = nearest_outside[i] {
+                    let other = if first == i { second } else { first };
+                    if component[other] != component[i] {
+                        if best.is_none_or(|best| compare_pairs(pair, best).is_lt()) {
+                            *best = Some(pair);
+                        }
+                        continue;
+                    }
+                }
+                // Only points closer than the best pair of the component so far are of interest.
+                let bound = *best;
+                let range = 0..self.len();
+                self.search_other_component(i, &component, &uniform, metric, range, 0, best);
+                nearest_outside[i] = if *best != bound { *best } else { None };
+            }
+            // Both ends of an edge may pick it, but different edges never form a cycle
+            // since ties are broken by index.
+            let mut chosen = closest.into_iter().flatten().collect::<Vec<_>>();
+            chosen.sort_unstable_by(|&a, &b| compare_pairs(a, b));
+            chosen.dedup_by(|a, b| compare_pairs(*a, *b).is_eq());
+            // Relabel every new component after one of its old ones.
+            let mut adjacent = vec![Vec::new(); self.len()];
+            for &(_, i, j) in &chosen {
+                adjacent[component[i]].push(component[j]);
+                adjacent[component[j]].push(component[i]);
+            }
+            let mut relabel = (0..self.len()).collect::<Vec<_>>();
+            let mut seen = vec![false; self.len()];
+            for root in 0..self.len() {
+                if seen[root] || adjacent[root].is_empty() {
+                    continue;
+                }
+                seen[root] = true;
+                let mut stack = vec![root];
+                while let Some(label) = stack.pop() {
+                    relabel[label] = root;
+                    for &other in &adjacent[label] {
+                        if !seen[other] {
+                            seen[other] = true;
+                            stack.push(other);
+                        }
+                    }
+                }
+            }
+            for label in &mut component {
+                *label = relabel[*label];
+            }
+            num_components -= chosen.len();
+            edges.extend(chosen);
+        }
+        edges.sort_unstable_by(|&a, &b| compare_pairs(a, b));
+        edges
+            .into_iter()
+            .map(|(distance, i, j)| (i, j, distance))
+            .collect()
+    }
+
+    /// For every subtree whose points all belong to the same component, records that component.
+    /// Subtrees are keyed by the position of their root in `order`, or their start for leaves.
+    fn uniform_components(&self, component: &[usize]) -> Vec<Option<usize>> {
+        let mut uniform = vec![None; self.len()];
+        if !self.is_empty() {
+            self.mark_uniform(component, 0..self.len(), &mut uniform);
+        }
+        uniform
+    }
+
+    fn mark_uniform(
+        &self,
+        component: &[usize],
+        range: std::ops::Range<usize>,
+        uniform: &mut [Option<usize>],
+    ) -> Option<usize> {
+        if range.len() <= LEAF_SIZE {
+            let first = component[self.order[range.start]];
+            let indices = &self.order[range.clone()];
+            let result = indices
+                .iter()
+                .all(|&index| component[index] == first)
+                .then_some(first);
+            uniform[range.start] = result;
+            return result;
+        }
+        let mid = range.start + range.len() / 2;
+        let left = self.mark_uniform(component, range.start..mid, uniform);
+        let right = self.mark_uniform(component, mid + 1..range.end, uniform);
+        let own = component[self.order[mid]];
+        let result = (left == Some(own) && right == Some(own)).then_some(own);
+        uniform[mid] = result;
+        result
+    }
+
+    /// Looks for a point closer to `index` than `best` that lies in another component,
+    /// skipping subtrees that lie entirely within its own component.
+    #[allow(clippy::too_many_arguments)]
+    fn search_other_component(
+        &self,
+        index: usize,
+        component: &[usize],
+        uniform: &[Option<usize>],
+        metric: Metric,
+        range: std::ops::Range<usize>,
+        depth: usize,
+        best: &mut Option<(T, usize, usize)>,
+    ) {
+        let own = component[index];
+        let query = &self.points[index];
+        let consider = |other: usize, best: &mut Option<(T, usize, usize)>| {
+            if component[other] != own {
+                let distance = metric.distance(query, &self.points[other]);
+                let candidate = (distance, index.min(other), index.max(other));
+                if best.is_none_or(|best| compare_pairs(candidate, best).is_lt()) {
+                    *best = Some(candidate);
+                }
+            }
+        };
+        if range.len() <= LEAF_SIZE {
+            if uniform[range.start] != Some(own) {
+                for &other in &self.order[range] {
+                    consider(other, best);
+                }
+            }
+            return;
+        }
+        let mid = range.start + range.len() / 2;
+        if uniform[mid] == Some(own) {
+            return;
+        }
+        let point = &self.points[self.order[mid]];
+        consider(self.order[mid], best);
+        let axis = depth % N;
+        let difference = query.coords[axis] - point.coords[axis];
+        let (near, far) = if difference < T::zero() {
+            (range.start..mid, mid + 1..range.end)
+        } else {
+            (mid + 1..range.end, range.start..mid)
+        };
+        self.search_other_component(index, component, uniform, metric, near, depth + 1, best);
+        if best.is_none_or(|(distance, _, _)| metric.axis_distance(difference) <= distance) {
+            self.search_other_component(index, component, uniform, metric, far, depth + 1, best);
+        }
+    }
+}
+
+/// Orders pairs `(distance, i, j)` by distance, then by `i`, then by `j`.
+fn compare_pairs<T: Num>(a: (T, usize, usize), b: (T, usize, usize)) -> Ordering {
+    compare(a.0, b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2))
+}
+
+/// Inserts a point into a list of the `k` nearest points so far,
+/// which is sorted by distance and then by index.
+fn insert_nearest<T: Num>(best: &mut Vec<(usize, T)>, k: usize, index: usize, distance: T) {
+    if best.len() == k
+        && compare(best[k - 1].1, distance)
+            .then(best[k - 1].0.cmp(&index))
+            .is_lt()
+    {
+        return;
+    }
+    let position = best.partition_point(|&(other, other_distance)| {
+        compare(other_distance, distance)
+            .then(other.cmp(&index))
+            .is_lt()
+    });
+    best.insert(position, (index, distance));
+    best.truncate(k);
+}
+
+fn build<const N: usize, T: Num>(points: &[Vector<N, T>], order: &mut [usize], depth: usize) {
+    if order.len() <= 1 {
+        return;
+    }
+    let axis = depth % N;
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |&a, &b| {
+        compare(points[a].coords[axis], points[b].coords[axis])
+    });
+    let (left, right) = order.split_at_mut(mid);
+    build(points, left, depth + 1);
+    build(points, &mut right[1..], depth + 1);
+}
+
+/// Iterator over all pairs of points in a [`KdTree`], see [`KdTree::pairs`].
+///
+/// Every point `i` has a stream of its neighbours `j > i`, closest first,
+/// fetched in growing batches. A queue holds the next pair of each stream.
+pub struct Pairs<'a, const N: usize, T: Num> {
+    tree: &'a KdTree<N, T>,
+    metric: Metric,
+    /// The neighbours of each point with a larger index fetched so far, closest first.
+    neighbours: Vec<Vec<(usize, T)>>,
+    /// For each point, the position in `neighbours` of the next pair to queue.
+    next: Vec<usize>,
+    queue: BinaryHeap<Reverse<QueuedPair<T>>>,
+}
+
+/// A pair `(distance, i, j)`, ordered as [`compare_pairs`] does.
+struct QueuedPair<T>(T, usize, usize);
+
+impl<T: Num> PartialEq for QueuedPair<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl<T: Num> Eq for QueuedPair<T> {}
+
+impl<T: Num> PartialOrd for QueuedPair<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Num> Ord for QueuedPair<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_pairs((self.0, self.1, self.2), (other.0, other.1, other.2))
+    }
+}
+
+impl<const N: usize, T: Num> Pairs<'_, N, T> {
+    /// Queues the next pair of point `i`, if it has any left.
+    fn advance(&mut self, i: usize) {
+        let remaining = self.tree.len() - i - 1;
+        if self.next[i] == self.neighbours[i].len() {
+            let fetched = self.neighbours[i].len();
+            if fetched == remaining {
+                self.neighbours[i] = Vec::new();
+                return;
+            }
+            let k = (fetched * 2).max(4).min(remaining);
+            let point = &self.tree.points[i];
+            let mut found = Vec::with_capacity(k + 1);
+            let range = 0..self.tree.len();
+            self.tree
+                .search_nearest(point, k, self.metric, i + 1, range, 0, &mut found);
+            self.neighbours[i] = found;
+        }
+        let (j, distance) = self.neighbours[i][self.next[i]];
+        self.next[i] += 1;
+        self.queue.push(Reverse(QueuedPair(distance, i, j)));
+    }
+}
+
+impl<const N: usize, T: Num> Iterator for Pairs<'_, N, T> {
+    type Item = (usize, usize, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse(QueuedPair(distance, i, j)) = self.queue.pop()?;
+        self.advance(i);
+        Some((i, j, distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Vector2D;
+
+    fn grid_points() -> Vec<Vector2D> {
+        (0..5)
+            .flat_map(|y| (0..5).map(move |x| Vector2D::new(x * 3 + y % 2, y * 2)))
+            .collect()
+    }
+
+    fn brute_force_nearest(
+        points: &[Vector2D],
+        query: &Vector2D,
+        metric: Metric,
+    ) -> Vec<(usize, i32)> {
+        let mut all = points
+            .iter()
+            .enumerate()
+            .map(|(i, point)| (i, metric.distance(query, point)))
+            .collect::<Vec<_>>();
+        all.sort_by_key(|&(i, distance)| (distance, i));
+        all
+    }
+
+    #[test]
+    fn test_nearest() {
+        let points = grid_points();
+        let tree = KdTree::new(&points);
+        for metric in [Metric::Euclidean, Metric::Manhattan] {
+            for query in [
+                Vector2D::new(0, 0),
+                Vector2D::new(7, 5),
+                Vector2D::new(-3, 20),
+            ] {
+                let expected = brute_force_nearest(&points, &query, metric);
+                assert_eq!(tree.nearest(&query, 5, metric), expected[..5]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_within_radius() {
+        let points = grid_points();
+        let tree = KdTree::new(&points);
+        let query = Vector2D::new(6, 4);
+        for (metric, radius) in [(Metric::Euclidean, 9), (Metric::Manhattan, 4)] {
+            let expected = brute_force_nearest(&points, &query, metric)
+                .into_iter()
+                .filter(|&(_, distance)| distance <= radius)
+                .collect::<Vec<_>>();
+            assert_eq!(tree.within_radius(&query, radius, metric), expected);
+        }
+    }
+
+    #[test]
+    fn test_pairs() {
+        let points = grid_points();
+        let tree = KdTree::new(&points);
+        let mut expected = Vec::new();
+        for i in 0..points.len() {
+            for j in i + 1..points.len() {
+                expected.push((i, j, points[i].euclidean_distance_squared(&points[j])));
+            }
+        }
+        expected.sort_by_key(|&(i, j, distance)| (distance, i, j));
+        assert_eq!(tree.pairs(Metric::Euclidean).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_minimum_spanning_tree() {
+        let mut points = grid_points();
+        points.push(Vector2D::new(100, -50));
+        let tree = KdTree::new(&points);
+        for metric in [Metric::Euclidean, Metric::Manhattan] {
+            // Kruskal's algorithm over all pairs, with the same tie-breaking.
+            let mut component = (0..points.len()).collect::<Vec<_>>();
+            let mut expected = Vec::new();
+            for (i, j, distance) in tree.pairs(metric) {
+                let (a, b) = (component[i], component[j]);
+                if a != b {
+                    component
+                        .iter_mut()
+                        .filter(|c| **c == b)
+                        .for_each(|c| *c = a);
+                    expected.push((i, j, distance));
+                }
+            }
+            assert_eq!(tree.minimum_spanning_tree(metric), expected);
+        }
+    }
+}
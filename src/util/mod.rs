@@ -1,13 +1,37 @@
 #![allow(unused_imports)]
 
+pub use compress::*;
 pub use direction::*;
+pub use exact_cover::*;
+pub use gf2::*;
+pub use graph::*;
+pub use grid::*;
+pub use ilp::*;
+pub use kd_tree::*;
 pub use math::*;
 pub use num::*;
+pub use polygon::*;
+pub use polyomino::*;
+pub use range_set::*;
+pub use render::*;
 pub use slice::*;
+pub use union_find::*;
 pub use vector::*;
 
+mod compress;
 mod direction;
+mod exact_cover;
+mod gf2;
+mod graph;
+mod grid;
+mod ilp;
+mod kd_tree;
 mod math;
 mod num;
+mod polygon;
+mod polyomino;
+mod range_set;
+mod render;
 mod slice;
+mod union_find;
 mod vector;
@@ -0,0 +1,469 @@
+use super::{CompressedGrid, Num, Vector2D, gcd};
+
+/// A line segment between two points, including both end points.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Segment<T: Num = i32> {
+    pub start: Vector2D<T>,
+    pub end: Vector2D<T>,
+}
+
+/// A simple polygon, given by its vertices in order.
+/// The last vertex connects back to the first one.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Polygon<T: Num = i32> {
+    vertices: Vec<Vector2D<T>>,
+}
+
+/// How to decide whether a point lies inside a polygon.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FillRule {
+    /// Inside if a ray from the point crosses the boundary an odd number of times.
+    EvenOdd,
+    /// Inside if the boundary winds around the point at least once.
+    NonZero,
+}
+
+/// Twice the signed area of the triangle `o`, `a`, `b`.
+/// Positive if `b` lies counterclockwise from `a` as seen from `o` (with the y-axis pointing up).
+fn cross<T: Num>(o: Vector2D<T>, a: Vector2D<T>, b: Vector2D<T>) -> T {
+    let (u, v) = (a - o, b - o);
+    u.x() * v.y() - u.y() * v.x()
+}
+
+fn sign<T: Num>(x: T) -> i32 {
+    if x > T::zero() {
+        1
+    } else if x < T::zero() {
+        -1
+    } else {
+        0
+    }
+}
+
+fn min<T: Num>(a: T, b: T) -> T {
+    if a < b { a } else { b }
+}
+
+fn max<T: Num>(a: T, b: T) -> T {
+    if a < b { b } else { a }
+}
+
+impl<T: Num> Segment<T> {
+    pub fn new(start: Vector2D<T>, end: Vector2D<T>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains_point(&self, point: Vector2D<T>) -> bool {
+        cross(self.start, self.end, point) == T::zero()
+            && min(self.start.x(), self.end.x()) <= point.x()
+            && point.x() <= max(self.start.x(), self.end.x())
+            && min(self.start.y(), self.end.y()) <= point.y()
+            && point.y() <= max(self.start.y(), self.end.y())
+    }
+
+    /// Whether the segments cross each other at a single point
+    /// that lies strictly inside both of them.
+    pub fn crosses(&self, other: &Self) -> bool {
+        let d1 = sign(cross(other.start, other.end, self.start));
+        let d2 = sign(cross(other.start, other.end, self.end));
+        let d3 = sign(cross(self.start, self.end, other.start));
+        let d4 = sign(cross(self.start, self.end, other.end));
+        d1 * d2 < 0 && d3 * d4 < 0
+    }
+
+    /// Whether the segments have at least one point in common.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.crosses(other)
+            || self.contains_point(other.start)
+            || self.contains_point(other.end)
+            || other.contains_point(self.start)
+            || other.contains_point(self.end)
+    }
+
+    /// Whether the segment passes through the interior of an axis-aligned rectangle.
+    fn intersects_open_rectangle(&self, min_corner: Vector2D<T>, max_corner: Vector2D<T>) -> bool {
+        // Separating axis theorem: the segment misses the rectangle
+        // if their projections on either axis or on the segment's normal don't overlap.
+        if max(self.start.x(), self.end.x()) <= min_corner.x()
+            || min(self.start.x(), self.end.x()) >= max_corner.x()
+            || max(self.start.y(), self.end.y()) <= min_corner.y()
+            || min(self.start.y(), self.end.y()) >= max_corner.y()
+        {
+            return false;
+        }
+        let corners = [
+            min_corner,
+            Vector2D::new(max_corner.x(), min_corner.y()),
+            max_corner,
+            Vector2D::new(min_corner.x(), max_corner.y()),
+        ];
+        let sides = corners.map(|corner| sign(cross(self.start, self.end, corner)));
+        sides.contains(&1) && sides.contains(&-1)
+    }
+}
+
+impl<T: Num> Polygon<T> {
+    pub fn new(vertices: Vec<Vector2D<T>>) -> Self {
+        assert!(!vertices.is_empty(), "polygon must have vertices");
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[Vector2D<T>] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Segment<T>> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(&start, &end)| Segment::new(start, end))
+    }
+
+    /// Twice the signed area, using the shoelace formula.
+    /// Positive if the vertices go counterclockwise (with the y-axis pointing up).
+    pub fn twice_signed_area(&self) -> T {
+        self.edges()
+            .map(|edge| edge.start.x() * edge.end.y() - edge.end.x() * edge.start.y())
+            .sum()
+    }
+
+    pub fn area(&self) -> f64 {
+        self.twice_signed_area().abs().as_() / 2.0
+    }
+
+    /// Number of lattice points on the boundary, for a polygon with integer vertices.
+    pub fn boundary_points(&self) -> T {
+        self.edges()
+            .map(|edge| {
+                let step = edge.end - edge.start;
+                gcd(step.x(), step.y())
+            })
+            .sum()
+    }
+
+    /// Number of lattice points strictly inside, for a polygon with integer vertices.
+    pub fn interior_points(&self) -> T {
+        // Pick's theorem: A = i + b/2 - 1
+        let two = T::one() + T::one();
+        (self.twice_signed_area().abs() - self.boundary_points() + two) / two
+    }
+
+    pub fn on_boundary(&self, point: Vector2D<T>) -> bool {
+        self.edges().any(|edge| edge.contains_point(point))
+    }
+
+    /// How many times the boundary winds counterclockwise around a point.
+    pub fn winding_number(&self, point: Vector2D<T>) -> i32 {
+        self.scaled_winding_number(point, T::one())
+    }
+
+    /// Whether a point lies inside the polygon or on its boundary.
+    pub fn contains(&self, point: Vector2D<T>, rule: FillRule) -> bool {
+        self.scaled_contains(point, T::one(), rule)
+    }
+
+    /// Whether an axis-aligned rectangle with the given opposite corners
+    /// lies entirely inside the polygon (including its boundary).
+    ///
+    /// The polygon is treated as a continuous shape, so a zero-width gap
+    /// between two edges counts as outside. To treat every lattice point
+    /// as a whole tile instead, use a [`TileMap`].
+    pub fn contains_rectangle(&self, first: Vector2D<T>, second: Vector2D<T>) -> bool {
+        let min_corner = Vector2D::new(min(first.x(), second.x()), min(first.y(), second.y()));
+        let max_corner = Vector2D::new(max(first.x(), second.x()), max(first.y(), second.y()));
+        let two = T::one() + T::one();
+        if min_corner.x() == max_corner.x() || min_corner.y() == max_corner.y() {
+            return self.contains_segment(Segment::new(min_corner, max_corner));
+        }
+        // If no edge passes through the rectangle's interior, then the interior
+        // is either entirely inside or entirely outside. Check its center to know which.
+        // (We double all coordinates, so the center lies on the lattice.)
+        !self
+            .edges()
+            .any(|edge| edge.intersects_open_rectangle(min_corner, max_corner))
+            && self.scaled_contains(min_corner + max_corner, two, FillRule::NonZero)
+    }
+
+    fn contains_segment(&self, segment: Segment<T>) -> bool {
+        if self.edges().any(|edge| edge.crosses(&segment)) {
+            return false;
+        }
+        // The segment can only leave the polygon where it touches a vertex,
+        // so check halfway between every pair of consecutive touching points.
+        let mut points = self
+            .vertices
+            .iter()
+            .copied()
+            .filter(|&vertex| segment.contains_point(vertex))
+            .chain([segment.start, segment.end])
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let two = T::one() + T::one();
+        self.contains(segment.start, FillRule::NonZero)
+            && points
+                .windows(2)
+                .all(|pair| self.scaled_contains(pair[0] + pair[1], two, FillRule::NonZero))
+    }
+
+    fn scaled_contains(&self, point: Vector2D<T>, scale: T, rule: FillRule) -> bool {
+        let on_boundary = self
+            .edges()
+            .any(|edge| Segment::new(edge.start * scale, edge.end * scale).contains_point(point));
+        if on_boundary {
+            return true;
+        }
+        let winding_number = self.scaled_winding_number(point, scale);
+        match rule {
+            FillRule::EvenOdd => winding_number % 2 != 0,
+            FillRule::NonZero => winding_number != 0,
+        }
+    }
+
+    /// Winding number of a point, with every vertex multiplied by `scale`.
+    fn scaled_winding_number(&self, point: Vector2D<T>, scale: T) -> i32 {
+        // Dan Sunday's algorithm: count upward crossings to the right of the point,
+        // minus downward crossings to the right of the point.
+        let mut winding_number = 0;
+        for edge in self.edges() {
+            let (start, end) = (edge.start * scale, edge.end * scale);
+            if start.y() <= point.y() {
+                if end.y() > point.y() && cross(start, end, point) > T::zero() {
+                    winding_number += 1;
+                }
+            } else if end.y() <= point.y() && cross(start, end, point) < T::zero() {
+                winding_number -= 1;
+            }
+        }
+        winding_number
+    }
+}
+
+/// The tiles covered by a polygon with horizontal and vertical edges, where every lattice point
+/// stands for a whole unit tile. A tile is covered if it lies inside the polygon or on its boundary,
+/// so two edges right next to each other leave no gap between them.
+#[derive(Debug, Clone)]
+pub struct TileMap {
+    grid: CompressedGrid,
+    /// The real area of the uncovered cells in the compressed rectangle from the origin
+    /// up to (but excluding) each cell, with a stride of `grid.width() + 1`.
+    uncovered: Vec<i64>,
+    num_tiles: i64,
+}
+
+impl Polygon<i64> {
+    /// Finds the tiles covered by the polygon, which must only have horizontal and vertical edges.
+    pub fn tile_map(&self) -> TileMap {
+        let grid = CompressedGrid::new(self.vertices.iter().copied());
+        // Draw the edges on the compressed grid. Every cell is then either entirely covered or not,
+        // since only the rows and columns of the vertices hold edges.
+        let mut boundary = vec![false; grid.width() * grid.height()];
+        for edge in self.edges() {
+            assert!(
+                edge.start.x() == edge.end.x() || edge.start.y() == edge.end.y(),
+                "edges must be horizontal or vertical"
+            );
+            let start = grid.compress(edge.start).unwrap();
+            let end = grid.compress(edge.end).unwrap();
+            for y in start.y().min(end.y())..=start.y().max(end.y()) {
+                for x in start.x().min(end.x())..=start.x().max(end.x()) {
+                    boundary[grid.index(Vector2D::new(x, y))] = true;
+                }
+            }
+        }
+        // The border around the grid is outside, and so is everything connected to it.
+        let outside = grid.flood_fill(Vector2D::zero(), |cell| !boundary[grid.index(cell)]);
+        let stride = grid.width() + 1;
+        let mut uncovered = vec![0; stride * (grid.height() + 1)];
+        for cell in grid.cells() {
+            let (x, y) = (cell.x() as usize, cell.y() as usize);
+            let area = if outside.filled[grid.index(cell)] {
+                grid.cell_area(cell)
+            } else {
+                0
+            };
+            uncovered[(y + 1) * stride + (x + 1)] =
+                area + uncovered[y * stride + (x + 1)] + uncovered[(y + 1) * stride + x]
+                    - uncovered[y * stride + x];
+        }
+        let total_area = grid.cells().map(|cell| grid.cell_area(cell)).sum::<i64>();
+        TileMap {
+            num_tiles: total_area - outside.area,
+            grid,
+            uncovered,
+        }
+    }
+}
+
+impl TileMap {
+    /// The number of covered tiles.
+    pub fn num_tiles(&self) -> i64 {
+        self.num_tiles
+    }
+
+    /// Whether every tile of the rectangle with the given opposite corners is covered.
+    pub fn contains_rectangle(&self, first: Vector2D<i64>, second: Vector2D<i64>) -> bool {
+        let min_corner = Vector2D::new(first.x().min(second.x()), first.y().min(second.y()));
+        let max_corner = Vector2D::new(first.x().max(second.x()), first.y().max(second.y()));
+        // Beyond the border, everything is outside.
+        let (Some(top_left), Some(bottom_right)) =
+            (self.grid.cell_of(min_corner), self.grid.cell_of(max_corner))
+        else {
+            return false;
+        };
+        let (x1, y1) = (top_left.x() as usize, top_left.y() as usize);
+        let (x2, y2) = (bottom_right.x() as usize + 1, bottom_right.y() as usize + 1);
+        let stride = self.grid.width() + 1;
+        self.uncovered[y2 * stride + x2] + self.uncovered[y1 * stride + x1]
+            == self.uncovered[y1 * stride + x2] + self.uncovered[y2 * stride + x1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An L-shaped polygon:
+    /// ```text
+    /// +--+
+    /// |  |
+    /// |  +--+
+    /// |     |
+    /// +-----+
+    /// ```
+    fn l_shape() -> Polygon {
+        Polygon::new(vec![
+            Vector2D::new(0, 0),
+            Vector2D::new(2, 0),
+            Vector2D::new(2, 2),
+            Vector2D::new(4, 2),
+            Vector2D::new(4, 4),
+            Vector2D::new(0, 4),
+        ])
+    }
+
+    #[test]
+    fn test_area() {
+        let polygon = l_shape();
+        assert_eq!(polygon.twice_signed_area(), 24);
+        assert_eq!(polygon.area(), 12.0);
+        let reversed = Polygon::new(polygon.vertices().iter().rev().copied().collect());
+        assert_eq!(reversed.twice_signed_area(), -24);
+    }
+
+    #[test]
+    fn test_lattice_points() {
+        let polygon = l_shape();
+        assert_eq!(polygon.boundary_points(), 16);
+        assert_eq!(polygon.interior_points(), 5);
+        let triangle = Polygon::new(vec![
+            Vector2D::new(0, 0),
+            Vector2D::new(4, 0),
+            Vector2D::new(0, 4),
+        ]);
+        assert_eq!(triangle.boundary_points(), 12);
+        assert_eq!(triangle.interior_points(), 3);
+    }
+
+    #[test]
+    fn test_contains() {
+        let polygon = l_shape();
+        assert!(polygon.contains(Vector2D::new(1, 1), FillRule::EvenOdd));
+        assert!(polygon.contains(Vector2D::new(3, 3), FillRule::NonZero));
+        assert!(polygon.contains(Vector2D::new(4, 3), FillRule::NonZero));
+        assert!(!polygon.contains(Vector2D::new(3, 1), FillRule::EvenOdd));
+        assert!(!polygon.contains(Vector2D::new(5, 3), FillRule::NonZero));
+        assert_eq!(polygon.winding_number(Vector2D::new(1, 1)), 1);
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        // A pentagram winds twice around its center.
+        let star = Polygon::new(vec![
+            Vector2D::new(0, 10),
+            Vector2D::new(6, -8),
+            Vector2D::new(-9, 3),
+            Vector2D::new(9, 3),
+            Vector2D::new(-6, -8),
+        ]);
+        assert_eq!(star.winding_number(Vector2D::new(0, 0)).abs(), 2);
+        assert!(star.contains(Vector2D::new(0, 0), FillRule::NonZero));
+        assert!(!star.contains(Vector2D::new(0, 0), FillRule::EvenOdd));
+    }
+
+    #[test]
+    fn test_segments() {
+        let first = Segment::new(Vector2D::new(0, 0), Vector2D::new(4, 4));
+        let second = Segment::new(Vector2D::new(0, 4), Vector2D::new(4, 0));
+        let third = Segment::new(Vector2D::new(2, 2), Vector2D::new(6, 2));
+        let fourth = Segment::new(Vector2D::new(5, 5), Vector2D::new(6, 6));
+        assert!(first.crosses(&second));
+        assert!(first.intersects(&second));
+        assert!(!first.crosses(&third));
+        assert!(first.intersects(&third));
+        assert!(!first.intersects(&fourth));
+    }
+
+    #[test]
+    fn test_contains_rectangle() {
+        let polygon = l_shape();
+        assert!(polygon.contains_rectangle(Vector2D::new(0, 0), Vector2D::new(2, 4)));
+        assert!(polygon.contains_rectangle(Vector2D::new(4, 4), Vector2D::new(0, 2)));
+        assert!(!polygon.contains_rectangle(Vector2D::new(0, 0), Vector2D::new(4, 4)));
+        assert!(!polygon.contains_rectangle(Vector2D::new(1, 1), Vector2D::new(3, 3)));
+        // Segments along the boundary or through the notch.
+        assert!(polygon.contains_rectangle(Vector2D::new(0, 0), Vector2D::new(2, 0)));
+        assert!(polygon.contains_rectangle(Vector2D::new(1, 2), Vector2D::new(4, 2)));
+        assert!(!polygon.contains_rectangle(Vector2D::new(2, 1), Vector2D::new(4, 1)));
+    }
+
+    #[test]
+    fn test_notch() {
+        // A U-shape: as a continuous shape, the gap between its arms is outside,
+        // even though every lattice point of the gap lies on the boundary.
+        let vertices = [
+            (0, 0),
+            (1, 0),
+            (1, 2),
+            (2, 2),
+            (2, 0),
+            (3, 0),
+            (3, 3),
+            (0, 3),
+        ];
+        let polygon = Polygon::new(vertices.map(|(x, y)| Vector2D::new(x, y)).to_vec());
+        assert!(!polygon.contains_rectangle(Vector2D::new(1, 0), Vector2D::new(2, 2)));
+        assert!(!polygon.contains_rectangle(Vector2D::new(1, 0), Vector2D::new(2, 0)));
+        assert!((1..=2).all(|x| (0..=2).all(|y| polygon.on_boundary(Vector2D::new(x, y)))));
+        assert!(polygon.contains_rectangle(Vector2D::new(0, 2), Vector2D::new(3, 3)));
+        // As tiles, the arms touch, so nothing is missing.
+        let polygon = Polygon::new(vertices.map(|(x, y)| Vector2D::new(x, y)).to_vec());
+        let tiles = polygon.tile_map();
+        assert_eq!(tiles.num_tiles(), 16);
+        assert!(tiles.contains_rectangle(Vector2D::new(1, 0), Vector2D::new(2, 2)));
+        assert!(tiles.contains_rectangle(Vector2D::new(3, 3), Vector2D::new(0, 0)));
+        assert!(!tiles.contains_rectangle(Vector2D::new(0, 0), Vector2D::new(4, 3)));
+    }
+
+    #[test]
+    fn test_tile_map() {
+        // An L-shape with long arms, so the rectangles end in the middle of compressed cells.
+        let polygon = Polygon::new(vec![
+            Vector2D::new(0, 0),
+            Vector2D::new(10, 0),
+            Vector2D::new(10, 10),
+            Vector2D::new(20, 10),
+            Vector2D::new(20, 20),
+            Vector2D::new(0, 20),
+        ]);
+        let tiles = polygon.tile_map();
+        assert_eq!(
+            tiles.num_tiles(),
+            polygon.boundary_points() + polygon.interior_points()
+        );
+        assert!(tiles.contains_rectangle(Vector2D::new(3, 2), Vector2D::new(7, 18)));
+        assert!(tiles.contains_rectangle(Vector2D::new(5, 15), Vector2D::new(20, 12)));
+        assert!(!tiles.contains_rectangle(Vector2D::new(5, 5), Vector2D::new(11, 15)));
+        assert!(!tiles.contains_rectangle(Vector2D::new(-1, 5), Vector2D::new(5, 5)));
+        assert!(!tiles.contains_rectangle(Vector2D::new(-5, 5), Vector2D::new(5, 5)));
+    }
+}
@@ -0,0 +1,268 @@
+use super::Vector2D;
+
+/// A shape made of unit squares, moved so that its bounding box starts at the origin.
+///
+/// The cells are kept in reading order, so two polyominoes are equal
+/// if and only if they have the same shape in the same orientation.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Polyomino {
+    cells: Vec<Vector2D>,
+    width: i32,
+    height: i32,
+}
+
+/// One of the 8 symmetries of a square: a rotation, optionally preceded by a mirror image.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Dihedral {
+    /// Whether to flip the shape horizontally first.
+    pub mirror: bool,
+    /// The number of quarter turns clockwise.
+    pub quarter_turns: u8,
+}
+
+impl Dihedral {
+    /// All 8 symmetries, starting with the identity.
+    pub fn all() -> impl Iterator<Item = Dihedral> {
+        [false, true].into_iter().flat_map(|mirror| {
+            (0..4).map(move |quarter_turns| Dihedral {
+                mirror,
+                quarter_turns,
+            })
+        })
+    }
+
+    pub fn apply(self, pos: Vector2D) -> Vector2D {
+        let mut pos = if self.mirror {
+            Vector2D::new(-pos.x(), pos.y())
+        } else {
+            pos
+        };
+        for _ in 0..self.quarter_turns {
+            pos = Vector2D::new(-pos.y(), pos.x());
+        }
+        pos
+    }
+}
+
+#[allow(dead_code)]
+impl Polyomino {
+    /// Creates a polyomino from its cells, in any order and at any position.
+    pub fn new(cells: impl IntoIterator<Item = Vector2D>) -> Self {
+        let mut cells = cells.into_iter().collect::<Vec<_>>();
+        assert!(!cells.is_empty(), "a polyomino needs at least one cell");
+        let min_x = cells.iter().map(|pos| pos.x()).min().unwrap();
+        let min_y = cells.iter().map(|pos| pos.y()).min().unwrap();
+        let origin = Vector2D::new(min_x, min_y);
+        for pos in &mut cells {
+            *pos -= origin;
+        }
+        cells.sort_unstable_by_key(|pos| (pos.y(), pos.x()));
+        cells.dedup();
+        let width = cells.iter().map(|pos| pos.x()).max().unwrap() + 1;
+        let height = cells.last().unwrap().y() + 1;
+        Self {
+            cells,
+            width,
+            height,
+        }
+    }
+
+    /// The cells, in reading order.
+    pub fn cells(&self) -> &[Vector2D] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The width of the bounding box.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// The height of the bounding box.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn transform(&self, transform: Dihedral) -> Self {
+        Self::new(self.cells.iter().map(|&pos| transform.apply(pos)))
+    }
+
+    /// All distinct rotations and mirror images, starting with this one.
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut orientations = Vec::<Self>::with_capacity(8);
+        for transform in Dihedral::all() {
+            let orientation = self.transform(transform);
+            if !orientations.contains(&orientation) {
+                orientations.push(orientation);
+            }
+        }
+        orientations
+    }
+
+    /// The same representative for all rotations and mirror images of a shape,
+    /// so they compare equal regardless of their orientation.
+    pub fn canonical(&self) -> Self {
+        Dihedral::all()
+            .map(|transform| self.transform(transform))
+            .min_by_key(|orientation| {
+                let cells = orientation.cells.iter();
+                cells.map(|pos| (pos.y(), pos.x())).collect::<Vec<_>>()
+            })
+            .unwrap()
+    }
+
+    pub fn bitboard(&self) -> Bitboard {
+        let mut bitboard = Bitboard::new(self.width as usize, self.height as usize);
+        for &pos in &self.cells {
+            bitboard.rows[pos.y() as usize] |= 1 << pos.x();
+        }
+        bitboard
+    }
+}
+
+/// A set of cells in a grid up to 64 wide, as one bitmask per row.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Bitboard {
+    width: usize,
+    /// Bit `x` of row `y` is set if the cell at `(x, y)` is filled.
+    rows: Vec<u64>,
+}
+
+#[allow(dead_code)]
+impl Bitboard {
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width <= 64, "bitboards can be at most 64 wide");
+        Self {
+            width,
+            rows: vec![0; height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn count(&self) -> usize {
+        self.rows.iter().map(|row| row.count_ones() as usize).sum()
+    }
+
+    pub fn contains(&self, pos: Vector2D) -> bool {
+        self.rows[pos.y() as usize] & (1 << pos.x()) != 0
+    }
+
+    /// Whether `other` fits inside this board when its top left corner is at `offset`.
+    pub fn fits(&self, other: &Bitboard, offset: Vector2D) -> bool {
+        offset.x() >= 0
+            && offset.y() >= 0
+            && offset.x() as usize + other.width <= self.width
+            && offset.y() as usize + other.height() <= self.height()
+    }
+
+    /// Whether `other` shares any filled cells with this board when placed at `offset`,
+    /// which must [fit](Self::fits).
+    pub fn overlaps(&self, other: &Bitboard, offset: Vector2D) -> bool {
+        let rows = &self.rows[offset.y() as usize..];
+        rows.iter()
+            .zip(&other.rows)
+            .any(|(row, other_row)| row & (other_row << offset.x()) != 0)
+    }
+
+    /// Fills the cells of `other` placed at `offset`, which must [fit](Self::fits).
+    pub fn place(&mut self, other: &Bitboard, offset: Vector2D) {
+        let rows = &mut self.rows[offset.y() as usize..];
+        for (row, other_row) in rows.iter_mut().zip(&other.rows) {
+            *row |= other_row << offset.x();
+        }
+    }
+
+    /// Clears the cells of `other` placed at `offset`, which must [fit](Self::fits).
+    pub fn remove(&mut self, other: &Bitboard, offset: Vector2D) {
+        let rows = &mut self.rows[offset.y() as usize..];
+        for (row, other_row) in rows.iter_mut().zip(&other.rows) {
+            *row &= !(other_row << offset.x());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(shape: &str) -> Polyomino {
+        Polyomino::new(shape.lines().enumerate().flat_map(|(y, line)| {
+            line.char_indices()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| Vector2D::new(x as i32, y as i32))
+        }))
+    }
+
+    #[test]
+    fn test_normalize() {
+        let shape = Polyomino::new([
+            Vector2D::new(5, 3),
+            Vector2D::new(4, 4),
+            Vector2D::new(5, 4),
+            Vector2D::new(5, 4),
+        ]);
+        assert_eq!(
+            shape.cells(),
+            [
+                Vector2D::new(1, 0),
+                Vector2D::new(0, 1),
+                Vector2D::new(1, 1)
+            ]
+        );
+        assert_eq!((shape.width(), shape.height()), (2, 2));
+    }
+
+    #[test]
+    fn test_orientations() {
+        assert_eq!(parse("#").orientations().len(), 1);
+        assert_eq!(parse("####").orientations().len(), 2);
+        assert_eq!(parse("##\n##").orientations().len(), 1);
+        assert_eq!(parse("###\n#..").orientations().len(), 8);
+        assert_eq!(parse("###\n.#.").orientations().len(), 4);
+        assert_eq!(parse(".##\n##.").orientations().len(), 4);
+        let long = parse("#####\n#....");
+        assert!(long.orientations().iter().any(|o| o.width() == 2));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let shape = parse("###\n#..");
+        let canonical = shape.canonical();
+        for orientation in shape.orientations() {
+            assert_eq!(orientation.canonical(), canonical);
+        }
+        assert_ne!(parse("###\n.#.").canonical(), canonical);
+    }
+
+    #[test]
+    fn test_bitboard() {
+        let shape = parse(".#\n##").bitboard();
+        let mut board = Bitboard::new(4, 3);
+        assert!(board.fits(&shape, Vector2D::new(2, 1)));
+        assert!(!board.fits(&shape, Vector2D::new(3, 0)));
+        board.place(&shape, Vector2D::new(0, 0));
+        assert!(board.overlaps(&shape, Vector2D::new(0, 1)));
+        assert!(board.overlaps(&shape, Vector2D::new(1, 0)));
+        assert!(!board.overlaps(&shape, Vector2D::new(2, 0)));
+        board.place(&shape, Vector2D::new(2, 0));
+        assert_eq!(board.count(), 6);
+        assert!(board.contains(Vector2D::new(3, 0)));
+        board.remove(&shape, Vector2D::new(0, 0));
+        assert_eq!(board.count(), 3);
+        assert!(!board.contains(Vector2D::new(0, 1)));
+    }
+}
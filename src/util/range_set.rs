@@ -0,0 +1,208 @@
+use num_traits::PrimInt;
+use std::ops::RangeInclusive;
+
+/// A set of integers, stored as a sorted list of disjoint inclusive ranges.
+///
+/// Ranges that overlap or touch each other are always merged,
+/// so every set has exactly one representation.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct RangeSet<T: PrimInt> {
+    ranges: Vec<RangeInclusive<T>>,
+}
+
+/// Whether a range starting at `right_start` directly follows one ending at `left_end`,
+/// assuming `left_end < right_start`.
+fn touches<T: PrimInt>(left_end: T, right_start: T) -> bool {
+    right_start - left_end == T::one()
+}
+
+#[allow(dead_code)]
+impl<T: PrimInt> RangeSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    pub fn ranges(&self) -> &[RangeInclusive<T>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The number of integers in the set.
+    pub fn len(&self) -> u128 {
+        self.ranges
+            .iter()
+            .map(|range| {
+                let start = range.start().to_i128().unwrap();
+                let end = range.end().to_i128().unwrap();
+                (end - start + 1) as u128
+            })
+            .sum()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.ranges.partition_point(|range| *range.end() < value);
+        self.ranges
+            .get(idx)
+            .is_some_and(|range| *range.start() <= value)
+    }
+
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        // Merge with every range that overlaps or touches the new one.
+        let first = self
+            .ranges
+            .partition_point(|range| *range.end() < start && !touches(*range.end(), start));
+        let last = self
+            .ranges
+            .partition_point(|range| *range.start() <= end || touches(end, *range.start()));
+        let merged = if first < last {
+            start.min(*self.ranges[first].start())..=end.max(*self.ranges[last - 1].end())
+        } else {
+            start..=end
+        };
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    pub fn remove(&mut self, range: RangeInclusive<T>) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        let first = self.ranges.partition_point(|range| *range.end() < start);
+        let last = self.ranges.partition_point(|range| *range.start() <= end);
+        if first >= last {
+            return;
+        }
+        // Keep whatever sticks out on either side of the removed range.
+        let mut remaining = Vec::with_capacity(2);
+        let (first_start, last_end) = (*self.ranges[first].start(), *self.ranges[last - 1].end());
+        if first_start < start {
+            remaining.push(first_start..=start - T::one());
+        }
+        if end < last_end {
+            remaining.push(end + T::one()..=last_end);
+        }
+        self.ranges.splice(first..last, remaining);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.insert(range.clone());
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (left, right) = (&self.ranges[i], &other.ranges[j]);
+            let start = *left.start().max(right.start());
+            let end = *left.end().min(right.end());
+            if start <= end {
+                ranges.push(start..=end);
+            }
+            // Move past whichever range ends first.
+            if left.end() < right.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        for range in &other.ranges {
+            result.remove(range.clone());
+        }
+        result
+    }
+
+    /// The ranges of missing integers between the first and the last range of the set.
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges
+            .windows(2)
+            .map(|pair| *pair[0].end() + T::one()..=*pair[1].start() - T::one())
+    }
+}
+
+impl<T: PrimInt> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt> FromIterator<RangeInclusive<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: PrimInt> Extend<RangeInclusive<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = RangeInclusive<T>>>(&mut self, iter: I) {
+        for range in iter {
+            self.insert(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert() {
+        let mut set = RangeSet::from_iter([10..=14, 3..=5, 16..=20, 12..=18]);
+        assert_eq!(set.ranges(), [3..=5, 10..=20]);
+        assert_eq!(set.len(), 14);
+        set.insert(6..=8);
+        assert_eq!(set.ranges(), [3..=8, 10..=20]);
+        set.insert(9..=9);
+        assert_eq!(set.ranges(), [3..=20]);
+        set.insert(u8::MAX..=u8::MAX);
+        assert_eq!(set.ranges(), [3..=20, 255..=255]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut set = RangeSet::from_iter([0..=10, 20..=30]);
+        set.remove(5..=24);
+        assert_eq!(set.ranges(), [0..=4, 25..=30]);
+        set.remove(0..=0);
+        set.remove(30..=40);
+        assert_eq!(set.ranges(), [1..=4, 25..=29]);
+        set.remove(2..=2);
+        assert_eq!(set.ranges(), [1..=1, 3..=4, 25..=29]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let set = RangeSet::from_iter([3..=5, 10..=20]);
+        assert!(set.contains(3));
+        assert!(set.contains(15));
+        assert!(!set.contains(2));
+        assert!(!set.contains(8));
+        assert!(!set.contains(21));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let left = RangeSet::from_iter([0..=10, 20..=30]);
+        let right = RangeSet::from_iter([5..=25, 40..=50]);
+        assert_eq!(left.union(&right).ranges(), [0..=30, 40..=50]);
+        assert_eq!(left.intersection(&right).ranges(), [5..=10, 20..=25]);
+        assert_eq!(left.difference(&right).ranges(), [0..=4, 26..=30]);
+        assert_eq!(left.union(&right).gaps().collect::<Vec<_>>(), vec![31..=39]);
+    }
+}
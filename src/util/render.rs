@@ -0,0 +1,100 @@
+use super::{Grid, Vector2D};
+use std::io::{self, Write};
+
+/// Characters for a text heatmap, from cold to hot.
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+/// Renders a grid as text, with one character per cell.
+#[allow(dead_code)]
+pub fn render_text<T>(grid: &Grid<T>, mut cell: impl FnMut(Vector2D, &T) -> char) -> String {
+    let mut output = String::with_capacity((grid.width() + 1) * grid.height());
+    for (pos, value) in grid.cells() {
+        output.push(cell(pos, value));
+        if pos.x() as usize == grid.width() - 1 {
+            output.push('\n');
+        }
+    }
+    output
+}
+
+/// Scales a count to a value between 0 and 1, on a logarithmic scale up to `max`.
+///
+/// Counts like the number of paths tend to grow exponentially,
+/// so a linear scale would leave everything but the hottest cells cold.
+fn heat(count: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    ((count as f64) + 1.0).ln() / ((max as f64) + 1.0).ln()
+}
+
+fn max_count(counts: &Grid<u64>) -> u64 {
+    counts.cells().map(|(_, &count)| count).max().unwrap_or(0)
+}
+
+/// Renders counts as a text heatmap. Cells with a count of zero are blank.
+#[allow(dead_code)]
+pub fn render_heatmap_text(counts: &Grid<u64>) -> String {
+    let max = max_count(counts);
+    render_text(counts, |_, &count| {
+        if count == 0 {
+            ' '
+        } else {
+            // Non-zero counts always get at least the first visible shade.
+            let shade = 1 + (heat(count, max) * (SHADES.len() - 2) as f64).round() as usize;
+            SHADES[shade.min(SHADES.len() - 1)] as char
+        }
+    })
+}
+
+/// Writes a grid as a binary PPM image, with one pixel per cell.
+#[allow(dead_code)]
+pub fn write_ppm<T>(
+    grid: &Grid<T>,
+    out: &mut impl Write,
+    mut color: impl FnMut(Vector2D, &T) -> [u8; 3],
+) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", grid.width(), grid.height())?;
+    for (pos, value) in grid.cells() {
+        out.write_all(&color(pos, value))?;
+    }
+    Ok(())
+}
+
+/// Writes counts as a heatmap in PPM format, going from black through red and yellow to white.
+#[allow(dead_code)]
+pub fn write_heatmap_ppm(counts: &Grid<u64>, out: &mut impl Write) -> io::Result<()> {
+    let max = max_count(counts);
+    write_ppm(counts, out, |_, &count| {
+        let heat = heat(count, max) * 3.0;
+        let channel = |offset: f64| ((heat - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+        [channel(0.0), channel(1.0), channel(2.0)]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_text() {
+        let grid = Grid::parse("ab\ncd\n", |c| c);
+        assert_eq!(
+            render_text(&grid, |_, &c| c.to_ascii_uppercase()),
+            "AB\nCD\n"
+        );
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let counts = Grid::new(4, 1, vec![0, 1, 10, 1000]);
+        assert_eq!(render_heatmap_text(&counts), " :=@\n");
+        let mut ppm = Vec::new();
+        write_heatmap_ppm(&counts, &mut ppm).unwrap();
+        let header = b"P6\n4 1\n255\n";
+        assert!(ppm.starts_with(header));
+        assert_eq!(ppm.len(), header.len() + 4 * 3);
+        assert_eq!(ppm[header.len()..][..3], [0, 0, 0]);
+        assert_eq!(ppm[ppm.len() - 3..], [255, 255, 255]);
+    }
+}
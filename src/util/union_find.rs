@@ -0,0 +1,157 @@
+/// A disjoint-set forest over the elements `0..len`, with union by size.
+///
+/// By default, lookups compress paths. A union-find created with [`UnionFind::with_rollback`]
+/// skips path compression instead, so that unions can be undone in reverse order.
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    /// The size of each component, only valid for roots.
+    size: Vec<usize>,
+    num_components: usize,
+    /// For every union so far, the root that was attached below another one.
+    history: Option<Vec<usize>>,
+}
+
+/// A point in the history of a [`UnionFind`] to roll back to.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Snapshot(usize);
+
+#[allow(dead_code)]
+impl UnionFind {
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+            num_components: len,
+            history: None,
+        }
+    }
+
+    /// Creates a union-find that supports [`rollback`](Self::rollback).
+    /// Lookups take `O(log n)` since paths aren't compressed.
+    pub fn with_rollback(len: usize) -> Self {
+        Self {
+            history: Some(Vec::new()),
+            ..Self::new(len)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    pub fn num_components(&self) -> usize {
+        self.num_components
+    }
+
+    /// The representative element of the component containing `x`.
+    pub fn find(&mut self, mut x: usize) -> usize {
+        if self.history.is_some() {
+            while self.parent[x] != x {
+                x = self.parent[x];
+            }
+            return x;
+        }
+        // Path halving: point every other element on the path to its grandparent.
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    pub fn same_component(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Merges the components containing `a` and `b`.
+    /// Returns `false` if they were already in the same component.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.num_components -= 1;
+        if let Some(history) = &mut self.history {
+            history.push(b);
+        }
+        true
+    }
+
+    pub fn component_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// The sizes of the `k` largest components, largest first.
+    pub fn largest_components(&self, k: usize) -> Vec<usize> {
+        let mut sizes = (0..self.len())
+            .filter(|&x| self.parent[x] == x)
+            .map(|root| self.size[root])
+            .collect::<Vec<_>>();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.truncate(k);
+        sizes
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let history = self.history.as_ref().expect("rollback is not enabled");
+        Snapshot(history.len())
+    }
+
+    /// Undoes all unions since the snapshot was taken.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        let history = self.history.as_mut().expect("rollback is not enabled");
+        while history.len() > snapshot.0 {
+            let child = history.pop().unwrap();
+            let root = self.parent[child];
+            self.parent[child] = child;
+            self.size[root] -= self.size[child];
+            self.num_components += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union() {
+        let mut uf = UnionFind::new(6);
+        assert!(uf.union(0, 1));
+        assert!(uf.union(2, 3));
+        assert!(uf.union(1, 3));
+        assert!(!uf.union(0, 2));
+        assert_eq!(uf.num_components(), 3);
+        assert!(uf.same_component(0, 3));
+        assert!(!uf.same_component(0, 4));
+        assert_eq!(uf.component_size(2), 4);
+        assert_eq!(uf.largest_components(2), vec![4, 1]);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut uf = UnionFind::with_rollback(5);
+        uf.union(0, 1);
+        let snapshot = uf.snapshot();
+        uf.union(1, 2);
+        uf.union(3, 4);
+        assert_eq!(uf.num_components(), 2);
+        uf.rollback(snapshot);
+        assert_eq!(uf.num_components(), 4);
+        assert!(uf.same_component(0, 1));
+        assert!(!uf.same_component(1, 2));
+        assert!(!uf.same_component(3, 4));
+        assert_eq!(uf.largest_components(5), vec![2, 1, 1, 1]);
+    }
+}